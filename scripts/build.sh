@@ -15,10 +15,20 @@ build_upgrader_canister() {
     gzip -k "$WASM_DIR/upgrader_canister.wasm" --force
 }
 
+build_archive_canister() {
+    echo "Building archive_canister"
+
+    cargo build -p archive_canister --target wasm32-unknown-unknown --release
+    ic-wasm $WASM_DIR/archive_canister.wasm -o $WASM_DIR/archive_canister.wasm shrink
+    candid-extractor $WASM_DIR/archive_canister.wasm > $WASM_DIR/archive_canister.did
+    gzip -k "$WASM_DIR/archive_canister.wasm" --force
+}
+
 main() {
     mkdir -p $WASM_DIR
 
     build_upgrader_canister
+    build_archive_canister
 
 }
 
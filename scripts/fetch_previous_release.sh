@@ -0,0 +1,48 @@
+#!/usr/bin/env sh
+set -e
+set -x #echo on
+
+# Provides the gzipped wasm of the previous release of the upgrader canister
+# in the fixtures directory used by the upgrade compatibility tests.
+# By default the wasm is built from the git tag of the previous release.
+# If an url is given as first argument, the wasm is downloaded from it instead.
+
+# The tag of the previous release, bumped when a new release is deployed.
+# candid/upgrader_canister.released.did must be the interface of the same release.
+PREVIOUS_RELEASE_TAG="v0.1.0"
+
+FIXTURES_DIR="src/upgrader_canister/tests/fixtures"
+FIXTURE_PATH="$FIXTURES_DIR/upgrader_canister_previous.wasm.gz"
+WORKTREE_DIR="target/previous_release"
+WASM_URL="$1"
+
+build_previous_release() {
+    git worktree remove --force "$WORKTREE_DIR" 2>/dev/null || true
+    if ! git rev-parse --verify --quiet "refs/tags/$PREVIOUS_RELEASE_TAG" >/dev/null; then
+        git fetch --depth=1 origin "refs/tags/$PREVIOUS_RELEASE_TAG:refs/tags/$PREVIOUS_RELEASE_TAG"
+    fi
+    git worktree add --detach "$WORKTREE_DIR" "$PREVIOUS_RELEASE_TAG"
+
+    (
+        cd "$WORKTREE_DIR"
+        cargo build -p upgrader_canister --target wasm32-unknown-unknown --release
+    )
+
+    WASM_PATH="$WORKTREE_DIR/target/wasm32-unknown-unknown/release/upgrader_canister.wasm"
+    ic-wasm "$WASM_PATH" -o "$WASM_PATH" shrink
+    gzip -c "$WASM_PATH" > "$FIXTURE_PATH"
+
+    git worktree remove --force "$WORKTREE_DIR"
+}
+
+main() {
+    mkdir -p $FIXTURES_DIR
+
+    if [ -z "$WASM_URL" ]; then
+        build_previous_release
+    else
+        curl --fail --location --output "$FIXTURE_PATH" "$WASM_URL"
+    fi
+}
+
+main "$@"
@@ -0,0 +1,52 @@
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use ic_exports::ic_cdk::{init, query, update};
+use ic_exports::ic_kit::ic;
+use upgrader_canister_did::error::Result;
+use upgrader_canister_did::{ArchiveCanisterInitData, ClosedPoll};
+
+use crate::state::ArchiveCanisterState;
+
+thread_local! {
+    pub static STATE: ArchiveCanisterState = ArchiveCanisterState::default();
+}
+
+#[init]
+pub fn init(data: ArchiveCanisterInitData) {
+    STATE.with(|state| state.polls.borrow_mut().set_upgrader(data.upgrader));
+}
+
+/// Returns the upgrader canister allowed to append polls
+#[query]
+pub fn upgrader_get() -> Principal {
+    STATE.with(|state| state.polls.borrow().upgrader())
+}
+
+/// Appends closed polls to the archive. Only the upgrader canister can call this method.
+#[update]
+pub fn polls_append(polls: Vec<(u64, ClosedPoll)>) -> Result<()> {
+    STATE.with(|state| state.polls.borrow_mut().append(&ic::caller(), polls))
+}
+
+/// Returns an archived poll by id
+#[query]
+pub fn poll_get(id: u64) -> Option<ClosedPoll> {
+    STATE.with(|state| state.polls.borrow().get(&id))
+}
+
+/// Returns the archived polls with id greater or equal than `start`.
+/// At most `POLLS_MAX_PAGE_SIZE` polls are returned.
+#[query]
+pub fn poll_get_range(start: u64, limit: u64) -> BTreeMap<u64, ClosedPoll> {
+    STATE.with(|state| state.polls.borrow().range(start, limit))
+}
+
+/// Returns the number of archived polls
+#[query]
+pub fn poll_count() -> u64 {
+    STATE.with(|state| state.polls.borrow().len())
+}
+
+// Enable Candid export
+ic_exports::ic_cdk::export_candid!();
@@ -0,0 +1,5 @@
+pub(crate) const UPGRADER_CELL_MEMORY_ID: u8 = 1;
+pub(crate) const POLLS_MAP_MEMORY_ID: u8 = 2;
+
+/// The maximum number of polls returned by a single query
+pub const POLLS_MAX_PAGE_SIZE: u64 = 100;
@@ -0,0 +1,3 @@
+pub mod canister;
+pub mod constant;
+pub mod state;
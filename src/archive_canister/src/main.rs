@@ -0,0 +1,3 @@
+pub use archive_canister::*;
+
+fn main() {}
@@ -0,0 +1,176 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use candid::Principal;
+use ic_stable_structures::stable_structures::{DefaultMemoryImpl, Memory};
+use ic_stable_structures::{
+    default_ic_memory_manager, BTreeMapStructure, CellStructure, MemoryManager, StableBTreeMap,
+    StableCell, VirtualMemory,
+};
+use upgrader_canister_did::error::{Result, UpgraderError};
+use upgrader_canister_did::ClosedPoll;
+
+use crate::constant::{POLLS_MAP_MEMORY_ID, POLLS_MAX_PAGE_SIZE, UPGRADER_CELL_MEMORY_ID};
+
+/// State of the archive canister
+pub struct ArchiveCanisterState {
+    pub polls: Rc<RefCell<ArchivedPolls<VirtualMemory<DefaultMemoryImpl>>>>,
+}
+
+impl Default for ArchiveCanisterState {
+    fn default() -> Self {
+        let memory_manager = default_ic_memory_manager();
+
+        Self {
+            polls: Rc::new(RefCell::new(ArchivedPolls::new(&memory_manager))),
+        }
+    }
+}
+
+/// Stores the closed polls moved from the upgrader canister
+pub struct ArchivedPolls<M: Memory> {
+    /// The upgrader canister allowed to append polls
+    upgrader: StableCell<Principal, M>,
+    /// The archived polls
+    polls: StableBTreeMap<u64, ClosedPoll, M>,
+}
+
+impl<M: Memory> ArchivedPolls<M> {
+    pub fn new(memory_manager: &dyn MemoryManager<M, u8>) -> Self {
+        Self {
+            upgrader: StableCell::new(
+                memory_manager.get(UPGRADER_CELL_MEMORY_ID),
+                Principal::anonymous(),
+            )
+            .expect("stable memory UPGRADER_CELL_MEMORY_ID initialization failed"),
+            polls: StableBTreeMap::new(memory_manager.get(POLLS_MAP_MEMORY_ID)),
+        }
+    }
+
+    /// Returns the upgrader canister allowed to append polls
+    pub fn upgrader(&self) -> Principal {
+        *self.upgrader.get()
+    }
+
+    /// Sets the upgrader canister allowed to append polls
+    pub fn set_upgrader(&mut self, upgrader: Principal) {
+        self.upgrader
+            .set(upgrader)
+            .expect("Unable to access the stable storage to set the upgrader canister");
+    }
+
+    /// Appends the polls to the archive.
+    /// Returns NotAuthorized error if the caller is not the upgrader canister.
+    pub fn append(&mut self, caller: &Principal, polls: Vec<(u64, ClosedPoll)>) -> Result<()> {
+        if caller != self.upgrader.get() {
+            return Err(UpgraderError::NotAuthorized);
+        }
+
+        for (id, poll) in polls {
+            self.polls.insert(id, poll);
+        }
+        Ok(())
+    }
+
+    /// Returns the archived poll with the given id
+    pub fn get(&self, id: &u64) -> Option<ClosedPoll> {
+        self.polls.get(id)
+    }
+
+    /// Returns up to `limit` archived polls with id greater or equal than `start`.
+    /// The number of returned polls is capped to `POLLS_MAX_PAGE_SIZE`.
+    pub fn range(&self, start: u64, limit: u64) -> BTreeMap<u64, ClosedPoll> {
+        self.polls
+            .range(start..)
+            .take(limit.min(POLLS_MAX_PAGE_SIZE) as usize)
+            .collect()
+    }
+
+    /// Returns the number of archived polls
+    pub fn len(&self) -> u64 {
+        self.polls.len()
+    }
+
+    /// Returns true if there are no archived polls
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::Principal;
+    use upgrader_canister_did::{ClosedPoll, PollResult, PollType};
+
+    use super::*;
+
+    fn closed_poll(description: &str) -> ClosedPoll {
+        ClosedPoll {
+            description: description.to_string(),
+            poll_type: PollType::ProjectHash {
+                project: "project".to_owned(),
+                hash: "hash".to_owned(),
+            },
+            no_voters: vec![],
+            yes_voters: vec![],
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+            result: PollResult::Rejected,
+        }
+    }
+
+    /// Should append the polls only if the caller is the upgrader canister
+    #[test]
+    fn test_append_polls() {
+        // Arrange
+        let mut polls = ArchivedPolls::new(&default_ic_memory_manager());
+        let upgrader = Principal::from_slice(&[1; 29]);
+        polls.set_upgrader(upgrader);
+
+        // Act & Assert
+        assert_eq!(
+            polls.append(
+                &Principal::from_slice(&[2; 29]),
+                vec![(0, closed_poll("poll_0"))]
+            ),
+            Err(UpgraderError::NotAuthorized)
+        );
+        assert!(polls.is_empty());
+
+        polls
+            .append(
+                &upgrader,
+                vec![(0, closed_poll("poll_0")), (3, closed_poll("poll_3"))],
+            )
+            .unwrap();
+        assert_eq!(polls.len(), 2);
+        assert_eq!(polls.get(&0), Some(closed_poll("poll_0")));
+        assert_eq!(polls.get(&3), Some(closed_poll("poll_3")));
+        assert_eq!(polls.get(&1), None);
+    }
+
+    /// Should return the polls in the requested range
+    #[test]
+    fn test_polls_range() {
+        // Arrange
+        let mut polls = ArchivedPolls::new(&default_ic_memory_manager());
+        let upgrader = Principal::from_slice(&[1; 29]);
+        polls.set_upgrader(upgrader);
+        polls
+            .append(
+                &upgrader,
+                (0..POLLS_MAX_PAGE_SIZE + 10)
+                    .map(|id| (id, closed_poll(&id.to_string())))
+                    .collect(),
+            )
+            .unwrap();
+
+        // Act & Assert
+        let range = polls.range(5, 2);
+        assert_eq!(range.keys().copied().collect::<Vec<_>>(), vec![5, 6]);
+        assert_eq!(polls.range(0, u64::MAX).len() as u64, POLLS_MAX_PAGE_SIZE);
+        assert_eq!(polls.range(POLLS_MAX_PAGE_SIZE, 100).len(), 10);
+    }
+}
@@ -2,6 +2,8 @@ use candid::CandidType;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
+use crate::Permission;
+
 pub type Result<T> = std::result::Result<T, UpgraderError>;
 
 #[derive(Debug, Error, Deserialize, CandidType, Eq, PartialEq, Serialize, Clone)]
@@ -17,4 +19,34 @@ pub enum UpgraderError {
 
     #[error("The key provided already exists: {0}")]
     NotUniqueKey(String),
+
+    #[error("The admin action requires the approval of other admins, pending action id: {0}")]
+    AdminApprovalRequired(u64),
+
+    #[error("The admin action with id {0} does not exist")]
+    AdminActionNotFound(u64),
+
+    #[error("The admin action is expired, it expired at {expired_at}")]
+    AdminActionExpired { expired_at: u64 },
+
+    #[error("The poll with id {0} does not exist")]
+    PollNotFound(u64),
+
+    #[error("The poll is not opened yet, it opens at {starts_at}")]
+    PollNotOpen { starts_at: u64 },
+
+    #[error("The poll is closed, it ended at {ended_at}")]
+    PollClosed { ended_at: u64 },
+
+    #[error("The poll is not ended yet, it ends at {ends_at}")]
+    PollNotEnded { ends_at: u64 },
+
+    #[error("The project [{0}] does not exist")]
+    ProjectNotFound(String),
+
+    #[error("The hash [{hash}] of the project [{project}] is revoked")]
+    ProjectHashRevoked { project: String, hash: String },
+
+    #[error("The user is missing the permissions {0:?}")]
+    MissingPermissions(Vec<Permission>),
 }
@@ -6,8 +6,10 @@ use serde::{Deserialize, Serialize};
 
 pub mod codec;
 pub mod error;
+pub mod wasm_hash;
 
 pub use error::*;
+pub use wasm_hash::WasmHash;
 
 /// Contains the build data.
 #[derive(Debug, Clone, Serialize, Deserialize, CandidType)]
@@ -28,6 +30,15 @@ pub struct BuildData {
 pub struct UpgraderCanisterInitData {
     /// Admin of the EVM Canister
     pub admin: Principal,
+    /// The network of the upgrader canister, e.g. `ic` or `local`.
+    /// Only the project canisters deployed on this network are inspected. Defaults to `ic`.
+    pub network: Option<String>,
+}
+
+#[derive(Debug, Clone, CandidType, Deserialize)]
+pub struct ArchiveCanisterInitData {
+    /// The upgrader canister allowed to append polls to the archive
+    pub upgrader: Principal,
 }
 
 /// Principal specific permission
@@ -69,6 +80,56 @@ pub struct ProjectData {
     pub name: String,
     /// The description of the project.
     pub description: String,
+    /// The canisters governed by the project.
+    pub canisters: Option<Vec<ProjectCanister>>,
+}
+
+/// A canister governed by a project.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct ProjectCanister {
+    /// The network or environment of the canister, e.g. `mainnet` or `testnet`.
+    pub network: String,
+    /// The id of the canister.
+    pub canister_id: Principal,
+}
+
+/// The deployment status of the canisters governed by a project.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct ProjectDeploymentStatus {
+    /// The key of the project.
+    pub project: String,
+    /// The latest hash approved for the project, if any.
+    pub latest_approved_hash: Option<String>,
+    /// The deployment status of each canister of the project.
+    pub canisters: Vec<CanisterDeploymentStatus>,
+}
+
+/// The deployment status of a canister governed by a project.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct CanisterDeploymentStatus {
+    /// The canister.
+    pub canister: ProjectCanister,
+    /// The hash of the module installed in the canister, if any.
+    pub module_hash: Option<String>,
+    /// The status of the installed module.
+    pub status: DeploymentStatus,
+}
+
+/// Describes the status of the module installed in a canister.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub enum DeploymentStatus {
+    /// The installed module is the latest approved hash.
+    Latest,
+    /// The installed module is approved but it is not the latest approved hash.
+    Outdated,
+    /// The installed module was approved and then revoked.
+    Revoked,
+    /// The installed module was never approved.
+    Unapproved,
+    /// No module is installed in the canister.
+    Empty,
+    /// The module hash could not be retrieved, it contains the reason of the failure.
+    Unknown(String),
 }
 
 impl Storable for ProjectData {
@@ -161,6 +222,9 @@ pub enum PollResult {
     Accepted,
     /// The poll is rejected.
     Rejected,
+    /// The poll is accepted but its action could not be applied,
+    /// it contains the reason of the failure.
+    Failed(String),
 }
 
 /// Describes the a poll already closed.
@@ -201,6 +265,8 @@ pub enum Poll {
     Pending(PendingPoll),
     /// The poll is closed.
     Closed(ClosedPoll),
+    /// The poll is closed and it was moved to the archive canister.
+    Archived { archive_canister: Principal },
 }
 
 impl From<PollCreateData> for PendingPoll {
@@ -219,7 +285,9 @@ impl From<PollCreateData> for PendingPoll {
 /// Describes the type of poll.
 #[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
 pub enum PollType {
-    /// A poll to approve a project hash
+    /// A poll to approve a project hash.
+    /// The hash is stored as text, like in the polls created before the hashes were validated;
+    /// it is validated and normalized with `WasmHash` when the poll is created.
     ProjectHash { project: String, hash: String },
     /// A poll to add permissions to principals
     AddPermission {
@@ -231,6 +299,245 @@ pub enum PollType {
         principals: Vec<Principal>,
         permissions: Vec<Permission>,
     },
+    /// A poll to execute multiple actions atomically
+    Batch(Vec<PollAction>),
+    /// A poll to update the canister settings
+    UpdateSettings(SettingsPatch),
+    /// A poll to create a new project
+    CreateProject(ProjectData),
+    /// A poll to update an existing project.
+    /// The whole project data is replaced, including the name, the description and the canisters.
+    UpdateProject(ProjectData),
+    /// A poll to revoke a previously approved project hash.
+    /// The hash is normalized with `WasmHash` when the poll is created.
+    RevokeProjectHash {
+        project: String,
+        hash: String,
+        reason: String,
+    },
+    /// A poll to approve again a revoked project hash.
+    /// A revoked hash cannot be approved by a `ProjectHash` poll.
+    /// The hash is normalized with `WasmHash` when the poll is created.
+    ReapproveProjectHash { project: String, hash: String },
+}
+
+/// Describes the status of a project hash.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub enum ProjectHashStatus {
+    /// The hash is approved by the poll with the given id.
+    Approved { poll_id: u64 },
+    /// The hash is revoked by the poll with the given id,
+    /// or by an admin if the poll id is `None`.
+    Revoked {
+        poll_id: Option<u64>,
+        reason: String,
+    },
+}
+
+/// A partial update of the canister settings.
+/// The fields set to `None` are left unchanged, while the poll duration bounds
+/// set to `Some(None)` are cleared.
+#[derive(Debug, Default, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct SettingsPatch {
+    /// Whether the inspect message is disabled.
+    pub disable_inspect_message: Option<bool>,
+    /// The minimum number of votes required for a poll to be accepted.
+    pub poll_quorum: Option<u64>,
+    /// The interval in seconds at which the poll timer finalizes the polls.
+    pub poll_timer_interval_secs: Option<u64>,
+    /// The minimum duration in seconds of a new poll, `Some(None)` removes the bound.
+    pub poll_min_duration_secs: Option<Option<u64>>,
+    /// The maximum duration in seconds of a new poll, `Some(None)` removes the bound.
+    pub poll_max_duration_secs: Option<Option<u64>>,
+}
+
+/// The settings applied to the polls.
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct PollSettings {
+    /// The minimum number of votes required for a poll to be accepted.
+    pub quorum: u64,
+    /// The minimum duration in seconds of a new poll, if any.
+    pub min_duration_secs: Option<u64>,
+    /// The maximum duration in seconds of a new poll, if any.
+    pub max_duration_secs: Option<u64>,
+}
+
+/// Describes an action executed by a batch poll.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub enum PollAction {
+    /// Approves a project hash, normalized with `WasmHash` when the poll is created
+    ProjectHash { project: String, hash: String },
+    /// Adds permissions to principals
+    AddPermission {
+        principals: Vec<Principal>,
+        permissions: Vec<Permission>,
+    },
+    /// Removes permissions from principals
+    RemovePermission {
+        principals: Vec<Principal>,
+        permissions: Vec<Permission>,
+    },
+}
+
+/// Settings of the multi-admin approval mode.
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct AdminApprovalSettings {
+    /// The number of distinct admins that must approve an admin action before it is executed.
+    pub threshold: u32,
+    /// The number of seconds after which a pending admin action expires.
+    pub expiry_secs: u64,
+}
+
+/// Settings of the archive of the closed polls.
+#[derive(Debug, Clone, Copy, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct ArchiveSettings {
+    /// The archive canister where the closed polls are moved.
+    pub canister_id: Principal,
+    /// The number of seconds a closed poll is kept in the upgrader canister after its end.
+    pub retention_secs: u64,
+}
+
+/// Describes an action that an admin can perform directly on the canister.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub enum AdminAction {
+    /// Adds permissions to a principal
+    PermissionsAdd {
+        principal: Principal,
+        permissions: Vec<Permission>,
+    },
+    /// Removes permissions from a principal
+    PermissionsRemove {
+        principal: Principal,
+        permissions: Vec<Permission>,
+    },
+    /// Disables/Enables the inspect message
+    DisableInspectMessage(bool),
+    /// Sets or removes the multi-admin approval settings
+    SetAdminApproval(Option<AdminApprovalSettings>),
+    /// Sets or removes the archive settings
+    SetArchive(Option<ArchiveSettings>),
+    /// Sets the interval in seconds at which the poll timer finalizes the polls
+    SetPollTimerInterval(u64),
+    /// Revokes an approved project hash
+    RevokeProjectHash {
+        project: String,
+        hash: String,
+        reason: String,
+    },
+}
+
+/// Describes an admin action waiting for the approval of other admins.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct PendingAdminAction {
+    /// The action to execute.
+    pub action: AdminAction,
+    /// The list of admins that approved the action.
+    pub approvals: Vec<Principal>,
+    /// The timestamp when the action was proposed.
+    pub created_timestamp_secs: u64,
+    /// The timestamp after which the action cannot be approved anymore.
+    pub expiry_timestamp_secs: u64,
+}
+
+impl Storable for PendingAdminAction {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        codec::encode(self).into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        codec::decode(&bytes)
+    }
+
+    const BOUND: ic_stable_structures::Bound = ic_stable_structures::Bound::Unbounded;
+}
+
+/// Describes a state-changing call recorded in the audit log.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct AuditLogEntry {
+    /// The principal that performed the call.
+    pub caller: Principal,
+    /// The name of the called method.
+    pub method: String,
+    /// A summary of the arguments of the call.
+    pub args: String,
+    /// The timestamp of the call.
+    pub timestamp_secs: u64,
+    /// The outcome of the call.
+    pub outcome: AuditLogOutcome,
+}
+
+impl Storable for AuditLogEntry {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        codec::encode(self).into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        codec::decode(&bytes)
+    }
+
+    const BOUND: ic_stable_structures::Bound = ic_stable_structures::Bound::Unbounded;
+}
+
+/// Describes the outcome of a call recorded in the audit log.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub enum AuditLogOutcome {
+    /// The call succeeded.
+    Success,
+    /// The call failed with the given error.
+    Failure(UpgraderError),
+}
+
+/// An alert recorded when a canister of a project runs a module that was never approved.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct UnapprovedUpgradeAlert {
+    /// The key of the project.
+    pub project: String,
+    /// The canister where the module was installed.
+    pub canister: ProjectCanister,
+    /// The hash of the installed module.
+    pub module_hash: String,
+    /// The version of the canister after the installation of the module.
+    pub canister_version: u64,
+    /// The timestamp in nanoseconds of the installation of the module.
+    pub installed_timestamp_nanos: u64,
+    /// The timestamp when the alert was recorded.
+    pub detected_timestamp_secs: u64,
+}
+
+impl Storable for UnapprovedUpgradeAlert {
+    fn to_bytes(&self) -> std::borrow::Cow<[u8]> {
+        codec::encode(self).into()
+    }
+
+    fn from_bytes(bytes: std::borrow::Cow<[u8]>) -> Self {
+        codec::decode(&bytes)
+    }
+
+    const BOUND: ic_stable_structures::Bound = ic_stable_structures::Bound::Unbounded;
+}
+
+/// An HTTP request received by the canister through the HTTP gateway.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct HttpRequest {
+    /// The HTTP method, e.g. `GET`.
+    pub method: String,
+    /// The request url, including the query string.
+    pub url: String,
+    /// The request headers.
+    pub headers: Vec<(String, String)>,
+    /// The request body.
+    pub body: Vec<u8>,
+}
+
+/// An HTTP response returned by the canister through the HTTP gateway.
+#[derive(Debug, Clone, CandidType, Deserialize, PartialEq, Eq, serde::Serialize)]
+pub struct HttpResponse {
+    /// The HTTP status code.
+    pub status_code: u16,
+    /// The response headers.
+    pub headers: Vec<(String, String)>,
+    /// The response body.
+    pub body: Vec<u8>,
 }
 
 #[cfg(test)]
@@ -270,6 +577,7 @@ mod test {
             key: "key".to_string(),
             name: "Project".to_string(),
             description: "Description".to_string(),
+            canisters: None,
         };
 
         let serialized = Encode!(&project).unwrap();
@@ -284,6 +592,7 @@ mod test {
             key: "key".to_string(),
             name: "Project".to_string(),
             description: "Description".to_string(),
+            canisters: None,
         };
 
         let serialized = project.to_bytes();
@@ -331,4 +640,84 @@ mod test {
 
         assert_eq!(poll, deserialized);
     }
+
+    #[test]
+    fn test_storable_batch_poll_data() {
+        let poll = PendingPoll {
+            description: "Description".to_string(),
+            poll_type: PollType::Batch(vec![
+                PollAction::AddPermission {
+                    principals: vec![Principal::from_slice(&[1u8; 29])],
+                    permissions: vec![Permission::VotePoll],
+                },
+                PollAction::ProjectHash {
+                    project: "project".to_string(),
+                    hash: "hash".to_string(),
+                },
+            ]),
+            no_voters: vec![],
+            yes_voters: vec![Principal::from_slice(&[2u8; 29])],
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        };
+
+        let serialized = poll.to_bytes();
+        let deserialized = PendingPoll::from_bytes(serialized);
+
+        assert_eq!(poll, deserialized);
+    }
+
+    #[test]
+    fn test_storable_pending_admin_action() {
+        let action = PendingAdminAction {
+            action: AdminAction::PermissionsAdd {
+                principal: Principal::from_slice(&[1u8; 29]),
+                permissions: vec![Permission::Admin],
+            },
+            approvals: vec![Principal::from_slice(&[2u8; 29])],
+            created_timestamp_secs: 0,
+            expiry_timestamp_secs: 1,
+        };
+
+        let serialized = action.to_bytes();
+        let deserialized = PendingAdminAction::from_bytes(serialized);
+
+        assert_eq!(action, deserialized);
+    }
+
+    #[test]
+    fn test_storable_audit_log_entry() {
+        let entry = AuditLogEntry {
+            caller: Principal::from_slice(&[1u8; 29]),
+            method: "poll_vote".to_string(),
+            args: "poll_id: 0, approved: true".to_string(),
+            timestamp_secs: 10,
+            outcome: AuditLogOutcome::Failure(UpgraderError::NotAuthorized),
+        };
+
+        let serialized = entry.to_bytes();
+        let deserialized = AuditLogEntry::from_bytes(serialized);
+
+        assert_eq!(entry, deserialized);
+    }
+
+    #[test]
+    fn test_storable_unapproved_upgrade_alert() {
+        let alert = UnapprovedUpgradeAlert {
+            project: "project".to_string(),
+            canister: ProjectCanister {
+                network: "ic".to_string(),
+                canister_id: Principal::from_slice(&[2u8; 10]),
+            },
+            module_hash: "00".repeat(32),
+            canister_version: 3,
+            installed_timestamp_nanos: 1_000_000_000,
+            detected_timestamp_secs: 10,
+        };
+
+        let serialized = alert.to_bytes();
+        let deserialized = UnapprovedUpgradeAlert::from_bytes(serialized);
+
+        assert_eq!(alert, deserialized);
+    }
 }
@@ -0,0 +1,119 @@
+use std::fmt;
+use std::str::FromStr;
+
+use candid::CandidType;
+use serde::{Deserialize, Serialize};
+
+use crate::error::UpgraderError;
+
+/// The SHA-256 hash of a wasm module.
+///
+/// It is parsed from its hex representation, optionally `0x` prefixed and in any case,
+/// and it is displayed as 64 lowercase hex characters, the same format of the module hash
+/// returned by the management canister.
+#[derive(
+    Debug, Clone, Copy, CandidType, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct WasmHash(pub [u8; 32]);
+
+impl WasmHash {
+    /// Returns the bytes of the hash
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Parses a hash from its hex representation and returns the canonical lowercase hex string
+    pub fn normalize(hash: &str) -> Result<String, UpgraderError> {
+        hash.parse::<Self>().map(|hash| hash.to_string())
+    }
+}
+
+impl FromStr for WasmHash {
+    type Err = UpgraderError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let hex = trimmed
+            .strip_prefix("0x")
+            .or_else(|| trimmed.strip_prefix("0X"))
+            .unwrap_or(trimmed);
+
+        if hex.len() != 64 {
+            return Err(UpgraderError::BadRequest(format!(
+                "The wasm hash [{s}] must be 64 hex characters long"
+            )));
+        }
+        if !hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return Err(UpgraderError::BadRequest(format!(
+                "The wasm hash [{s}] contains invalid hex characters"
+            )));
+        }
+
+        let mut bytes = [0u8; 32];
+        for (byte, chunk) in bytes.iter_mut().zip(hex.as_bytes().chunks(2)) {
+            // The chunk contains only ascii hex digits
+            *byte = (hex_digit_value(chunk[0]) << 4) | hex_digit_value(chunk[1]);
+        }
+
+        Ok(Self(bytes))
+    }
+}
+
+/// Returns the value of an ascii hex digit
+fn hex_digit_value(digit: u8) -> u8 {
+    match digit {
+        b'0'..=b'9' => digit - b'0',
+        b'a'..=b'f' => digit - b'a' + 10,
+        _ => digit - b'A' + 10,
+    }
+}
+
+impl fmt::Display for WasmHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<[u8; 32]> for WasmHash {
+    fn from(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const HASH: &str = "0a1b2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f9";
+
+    #[test]
+    fn test_parse_and_display_wasm_hash() {
+        let hash = HASH.parse::<WasmHash>().unwrap();
+        assert_eq!(hash.as_bytes()[0], 0x0a);
+        assert_eq!(hash.as_bytes()[31], 0xf9);
+        assert_eq!(hash.to_string(), HASH);
+    }
+
+    #[test]
+    fn test_normalize_wasm_hash() {
+        assert_eq!(WasmHash::normalize(HASH).unwrap(), HASH);
+        assert_eq!(WasmHash::normalize(&HASH.to_uppercase()).unwrap(), HASH);
+        assert_eq!(WasmHash::normalize(&format!("0x{HASH}")).unwrap(), HASH);
+        assert_eq!(WasmHash::normalize(&format!(" {HASH}\n")).unwrap(), HASH);
+    }
+
+    #[test]
+    fn test_reject_malformed_wasm_hash() {
+        assert!(WasmHash::normalize("").is_err());
+        assert!(WasmHash::normalize("hash").is_err());
+        assert!(WasmHash::normalize(&HASH[..62]).is_err());
+        assert!(WasmHash::normalize(&format!("{HASH}00")).is_err());
+        assert!(WasmHash::normalize(&HASH.replace('a', "g")).is_err());
+        assert!(WasmHash::normalize(&format!("{}é", &HASH[..62])).is_err());
+        assert!(WasmHash::normalize(&format!("+f{}", &HASH[2..])).is_err());
+    }
+}
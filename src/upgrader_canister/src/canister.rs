@@ -1,26 +1,40 @@
+use std::cell::Cell;
 use std::collections::BTreeMap;
+use std::time::Duration;
 
 use candid::Principal;
+use ic_exports::ic_cdk::api::management_canister::main::{
+    canister_info, CanisterChangeDetails, CanisterInfoRequest,
+};
 use ic_exports::ic_cdk::{init, post_upgrade, query, update};
+use ic_exports::ic_cdk_timers::TimerId;
 use ic_exports::ic_kit::ic;
-use ic_stable_structures::stable_structures::Memory;
-use log::info;
+use log::{error, info, warn};
 use upgrader_canister_did::error::Result;
 use upgrader_canister_did::{
-    BuildData, ClosedPoll, PendingPoll, Permission, PermissionList, Poll, PollCreateData, PollType,
-    ProjectData, UpgraderCanisterInitData, UpgraderError,
+    AdminApprovalSettings, ArchiveSettings, AuditLogEntry, BuildData, CanisterDeploymentStatus,
+    ClosedPoll, DeploymentStatus, HttpRequest, HttpResponse, PendingAdminAction, PendingPoll,
+    Permission, PermissionList, Poll, PollCreateData, PollSettings, ProjectData,
+    ProjectDeploymentStatus, ProjectHashStatus, UnapprovedUpgradeAlert, UpgraderCanisterInitData,
+    UpgraderError, WasmHash,
 };
 
-use crate::constant::POLL_TIMER_INTERVAL;
-use crate::state::permission::Permissions;
+use crate::constant::{
+    ALERTS_TIMER_INTERVAL, ARCHIVE_BATCH_SIZE, ARCHIVE_TIMER_INTERVAL, CANISTER_INFO_MAX_CHANGES,
+    POLL_FINALIZATION_INSTRUCTIONS_LIMIT, POLL_FINALIZATION_TIMER_MAX_DELAY,
+};
+use crate::endpoints;
+use crate::state::alerts::ModuleDeployment;
 use crate::state::UpgraderCanisterState;
 
 thread_local! {
     pub static STATE: UpgraderCanisterState = UpgraderCanisterState::default();
+    static POLL_TIMER_ID: Cell<Option<TimerId>> = const { Cell::new(None) };
 }
 
 #[post_upgrade]
 pub fn post_upgrade() {
+    index_approved_project_hashes();
     set_timers();
 }
 
@@ -31,87 +45,275 @@ pub fn init(data: UpgraderCanisterInitData) {
         permissions
             .add_permissions(data.admin, vec![Permission::Admin])
             .expect("failed to add admin permission");
+        state.settings.borrow_mut().set_network(data.network);
     });
     set_timers();
 }
 
+/// Indexes the project hashes approved before the introduction of the approved hashes index
+fn index_approved_project_hashes() {
+    STATE.with(|state| {
+        let mut projects = state.projects.borrow_mut();
+        if !projects.has_no_hashes() {
+            return;
+        }
+
+        for (poll_id, project, hash) in state.polls.borrow().accepted_project_hashes() {
+            let normalized_hash = match WasmHash::normalize(&hash) {
+                Ok(normalized_hash) => normalized_hash,
+                Err(err) => {
+                    warn!("Skipped the invalid hash {hash} of the project {project}: {err}");
+                    continue;
+                }
+            };
+            if let Err(err) = projects.approve_hash(&project, &normalized_hash, poll_id) {
+                error!("Failed to index the hash {hash} of the project {project}: {err}");
+            }
+        }
+    });
+}
+
 /// Initializes the timers
 fn set_timers() {
     // This block of code only need to be run in the wasm environment
     if cfg!(target_family = "wasm") {
         use ic_exports::ic_cdk_timers::set_timer_interval;
 
-        set_timer_interval(POLL_TIMER_INTERVAL, move || {
-            STATE.with(|state| {
-                let mut permissions = state.permissions.borrow_mut();
-                state
-                    .polls
-                    .borrow_mut()
-                    .finalize_polls(time_secs(), &mut permissions)
-                    .expect("Finalize polls error");
-            });
+        set_poll_timer_interval();
+
+        // Timers do not survive upgrades, the finalization of the pending polls is scheduled again
+        STATE.with(|state| {
+            for poll in state.polls.borrow().all_pending().values() {
+                schedule_poll_finalization(poll.end_timestamp_secs);
+            }
+        });
+
+        set_timer_interval(ARCHIVE_TIMER_INTERVAL, || {
+            ic_exports::ic_cdk::spawn(archive_closed_polls());
+        });
+
+        set_timer_interval(ALERTS_TIMER_INTERVAL, || {
+            ic_exports::ic_cdk::spawn(check_unapproved_upgrades());
         });
     } else {
         info!("Not setting timers as not in wasm environment");
     }
 }
 
+/// Sets the interval timer that finalizes the polls using the interval from the settings.
+/// The previous interval timer, if any, is cleared.
+fn set_poll_timer_interval() {
+    // This block of code only need to be run in the wasm environment
+    if cfg!(target_family = "wasm") {
+        use ic_exports::ic_cdk_timers::{clear_timer, set_timer_interval};
+
+        let interval = STATE.with(|state| state.settings.borrow().poll_timer_interval());
+        let timer_id = set_timer_interval(interval, finalize_polls);
+        if let Some(previous_timer_id) = POLL_TIMER_ID.with(|id| id.replace(Some(timer_id))) {
+            clear_timer(previous_timer_id);
+        }
+    }
+}
+
+/// Schedules a one-shot timer that finalizes the polls right after the given end timestamp.
+/// Polls ending after `POLL_FINALIZATION_TIMER_MAX_DELAY` are finalized by the interval timer.
+fn schedule_poll_finalization(end_timestamp_secs: u64) {
+    // This block of code only need to be run in the wasm environment
+    if cfg!(target_family = "wasm") {
+        use ic_exports::ic_cdk_timers::set_timer;
+
+        // A poll is closed only when the current time is greater than its end timestamp
+        let delay = Duration::from_secs(end_timestamp_secs.saturating_add(1))
+            .saturating_sub(Duration::from_secs(time_secs()));
+        if delay <= POLL_FINALIZATION_TIMER_MAX_DELAY {
+            set_timer(delay, finalize_polls);
+        }
+    }
+}
+
+/// Finalizes the polls whose end timestamp is passed.
+/// If the instructions limit is reached, the finalization is resumed by a new timer.
+fn finalize_polls() {
+    let has_polls_to_finalize = STATE.with(|state| {
+        state.last_timer_run_secs.set(time_secs());
+        finalize_due_polls(state);
+        state.polls.borrow().has_polls_to_finalize(time_secs())
+    });
+
+    // This block of code only need to be run in the wasm environment
+    if has_polls_to_finalize && cfg!(target_family = "wasm") {
+        use ic_exports::ic_cdk_timers::set_timer;
+
+        set_timer(Duration::ZERO, finalize_polls);
+    }
+}
+
+/// Finalizes the polls whose end timestamp is passed
+/// until the `POLL_FINALIZATION_INSTRUCTIONS_LIMIT` is reached
+fn finalize_due_polls(state: &UpgraderCanisterState) {
+    let previous_interval = state.settings.borrow().poll_timer_interval();
+    endpoints::finalize_due_polls(state, time_secs(), || {
+        instruction_counter() < POLL_FINALIZATION_INSTRUCTIONS_LIMIT
+    });
+    reset_poll_timer_interval_if_changed(state, previous_interval);
+}
+
+/// Resets the poll timer if its interval was changed by an `UpdateSettings` poll
+fn reset_poll_timer_interval_if_changed(state: &UpgraderCanisterState, previous: Duration) {
+    if state.settings.borrow().poll_timer_interval() != previous {
+        set_poll_timer_interval();
+    }
+}
+
 /// Returns the build data of the canister
 #[query]
 pub fn canister_build_data() -> BuildData {
     crate::build_data::canister_build_data()
 }
 
+/// Serves the read-only governance dashboard through the HTTP gateway
+#[query]
+pub fn http_request(request: HttpRequest) -> HttpResponse {
+    STATE.with(|state| crate::http::http_request(state, &request))
+}
+
 /// Returns the permissions of a principal
 #[query]
 pub fn admin_permissions_get(principal: Principal) -> Result<PermissionList> {
-    STATE.with(|state| {
-        let permissions = state.permissions.borrow();
-        permissions.check_admin(&ic::caller())?;
-        Ok(permissions.get_permissions(&principal))
-    })
+    STATE.with(|state| endpoints::admin_permissions_get(state, ic::caller(), principal))
 }
 
-/// Adds permissions to a principal and returns the principal permissions
+/// Adds permissions to a principal and returns the principal permissions.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
 #[update]
 pub fn admin_permissions_add(
     principal: Principal,
     permissions: Vec<Permission>,
 ) -> Result<PermissionList> {
     STATE.with(|state| {
-        state.permissions.borrow().check_admin(&ic::caller())?;
-        state
-            .permissions
-            .borrow_mut()
-            .add_permissions(principal, permissions)
+        endpoints::admin_permissions_add(state, ic::caller(), time_secs(), principal, permissions)
     })
 }
 
-/// Removes permissions from a principal and returns the principal permissions
+/// Removes permissions from a principal and returns the principal permissions.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
 #[update]
 pub fn admin_permissions_remove(
     principal: Principal,
     permissions: Vec<Permission>,
 ) -> Result<PermissionList> {
     STATE.with(|state| {
-        state.permissions.borrow().check_admin(&ic::caller())?;
-        state
-            .permissions
-            .borrow_mut()
-            .remove_permissions(principal, &permissions)
+        endpoints::admin_permissions_remove(
+            state,
+            ic::caller(),
+            time_secs(),
+            principal,
+            permissions,
+        )
     })
 }
 
-/// Disable/Enable the inspect message
+/// Disable/Enable the inspect message.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
 #[update]
 pub fn admin_disable_inspect_message(value: bool) -> Result<()> {
     STATE.with(|state| {
-        state.permissions.borrow().check_admin(&ic::caller())?;
-        state.settings.borrow_mut().disable_inspect_message(value);
-        Ok(())
+        endpoints::admin_disable_inspect_message(state, ic::caller(), time_secs(), value)
+    })
+}
+
+/// Sets the multi-admin approval settings. Passing `None` disables the multi-admin approval.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+#[update]
+pub fn admin_approval_settings_set(approval: Option<AdminApprovalSettings>) -> Result<()> {
+    STATE.with(|state| {
+        endpoints::admin_approval_settings_set(state, ic::caller(), time_secs(), approval)
     })
 }
 
+/// Sets the archive settings. Passing `None` disables the archiving of the closed polls.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+#[update]
+pub fn admin_archive_settings_set(archive: Option<ArchiveSettings>) -> Result<()> {
+    STATE.with(|state| {
+        endpoints::admin_archive_settings_set(state, ic::caller(), time_secs(), archive)
+    })
+}
+
+/// Sets the interval in seconds at which the poll timer finalizes the polls.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+#[update]
+pub fn admin_poll_timer_interval_set(interval_secs: u64) -> Result<()> {
+    STATE.with(|state| {
+        let previous_interval = state.settings.borrow().poll_timer_interval();
+        let result = endpoints::admin_poll_timer_interval_set(
+            state,
+            ic::caller(),
+            time_secs(),
+            interval_secs,
+        );
+        reset_poll_timer_interval_if_changed(state, previous_interval);
+        result
+    })
+}
+
+/// Returns the interval in seconds at which the poll timer finalizes the polls
+#[query]
+pub fn poll_timer_interval_get() -> u64 {
+    STATE.with(|state| state.settings.borrow().poll_timer_interval().as_secs())
+}
+
+/// Returns the settings applied to the polls
+#[query]
+pub fn poll_settings_get() -> PollSettings {
+    STATE.with(|state| state.settings.borrow().poll_settings())
+}
+
+/// Returns the archive settings, if the archiving of the closed polls is enabled
+#[query]
+pub fn archive_settings_get() -> Option<ArchiveSettings> {
+    STATE.with(|state| state.settings.borrow().archive())
+}
+
+/// Returns the multi-admin approval settings, if the multi-admin approval is enabled
+#[query]
+pub fn admin_approval_settings_get() -> Option<AdminApprovalSettings> {
+    STATE.with(|state| state.settings.borrow().admin_approval())
+}
+
+/// Returns all the pending admin actions that are not expired
+#[query]
+pub fn admin_action_get_all_pending() -> Result<BTreeMap<u64, PendingAdminAction>> {
+    STATE.with(|state| endpoints::admin_action_get_all_pending(state, ic::caller(), time_secs()))
+}
+
+/// Approves a pending admin action.
+/// The action is executed as soon as the required number of distinct admins approved it.
+/// Returns true if the action was executed.
+#[update]
+pub fn admin_action_approve(id: u64) -> Result<bool> {
+    STATE.with(|state| {
+        let previous_interval = state.settings.borrow().poll_timer_interval();
+        let result = endpoints::admin_action_approve(state, ic::caller(), time_secs(), id);
+        reset_poll_timer_interval_if_changed(state, previous_interval);
+        result
+    })
+}
+
+/// Returns the audit log entries starting from the `start` index.
+/// At most `AUDIT_LOG_MAX_PAGE_SIZE` entries are returned.
+/// The entries expose the arguments and the errors of the calls, so only the admins can read them.
+#[query]
+pub fn audit_log_get(start: u64, limit: u64) -> Result<BTreeMap<u64, AuditLogEntry>> {
+    STATE.with(|state| endpoints::audit_log_get(state, ic::caller(), start, limit))
+}
+
+/// Returns the number of entries in the audit log
+#[query]
+pub fn audit_log_len() -> Result<u64> {
+    STATE.with(|state| endpoints::audit_log_len(state, ic::caller()))
+}
+
 /// Returns whether the inspect message is disabled.
 #[query]
 pub fn is_inspect_message_disabled() -> bool {
@@ -139,21 +341,10 @@ pub fn project_get(key: String) -> Option<ProjectData> {
     STATE.with(|state| state.projects.borrow().get(&key))
 }
 
-/// Inspects permissions for the project_create method
-pub fn project_create_inspect<M: Memory>(
-    permissions: &Permissions<M>,
-    caller: &Principal,
-) -> Result<()> {
-    permissions.check_has_all_permissions(caller, &[Permission::CreateProject])
-}
-
 /// Creates a new project
 #[update]
 pub fn project_create(project: ProjectData) -> Result<()> {
-    STATE.with(|state| {
-        project_create_inspect(&state.permissions.borrow(), &ic::caller())?;
-        state.projects.borrow_mut().insert(project)
-    })
+    STATE.with(|state| endpoints::project_create(state, ic::caller(), time_secs(), project))
 }
 
 /// Returns all pending polls
@@ -168,12 +359,19 @@ pub fn poll_get_all_closed() -> BTreeMap<u64, ClosedPoll> {
     STATE.with(|state| state.polls.borrow().all_closed())
 }
 
-/// Returns a poll by id
+/// Returns a poll by id.
+/// If the poll was moved to the archive canister, the archive canister id is returned instead.
 #[query]
 pub fn poll_get(id: u64) -> Option<Poll> {
     STATE.with(|state| state.polls.borrow().get(&id))
 }
 
+/// Returns the id of the archive canister where the poll was moved, if it was archived
+#[query]
+pub fn poll_get_archive_canister(id: u64) -> Option<Principal> {
+    STATE.with(|state| state.polls.borrow().get_archive_canister(&id))
+}
+
 /// Returns a poll by id searching in the pending polls
 #[query]
 pub fn poll_get_pending(id: u64) -> Option<PendingPoll> {
@@ -186,54 +384,290 @@ pub fn poll_get_closed(id: u64) -> Option<ClosedPoll> {
     STATE.with(|state| state.polls.borrow().get_closed(&id))
 }
 
-/// Inspects permissions for the poll_create method
-pub fn poll_create_inspect<M: Memory>(
-    permissions: &Permissions<M>,
-    caller: &Principal,
-) -> Result<()> {
-    permissions.check_has_all_permissions(caller, &[Permission::CreatePoll])
+/// Returns the status of a project hash, if it was ever approved
+#[query]
+pub fn project_hash_status(project: String, hash: String) -> Option<ProjectHashStatus> {
+    let hash = WasmHash::normalize(&hash).ok()?;
+    STATE.with(|state| state.projects.borrow().hash_status(&project, &hash))
 }
 
-/// Creates a new poll and returns the generated poll id
+/// Returns the status of all the approved and revoked hashes of a project
+#[query]
+pub fn project_hashes_get(project: String) -> BTreeMap<String, ProjectHashStatus> {
+    STATE.with(|state| state.projects.borrow().hashes(&project))
+}
+
+/// Revokes an approved project hash without waiting for a poll.
+/// It is meant for emergencies, like a vulnerability found in the approved wasm.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
 #[update]
-pub fn poll_create(poll: PollCreateData) -> Result<u64> {
+pub fn admin_project_hash_revoke(project: String, hash: String, reason: String) -> Result<()> {
     STATE.with(|state| {
-        poll_create_inspect(&state.permissions.borrow(), &ic::caller())?;
-
-        if let PollType::ProjectHash { project, hash: _ } = &poll.poll_type {
-            state.projects.borrow().get(project).ok_or_else(|| {
-                UpgraderError::BadRequest(format!(
-                    "Cannot create poll, project [{}] does not exist",
-                    project
-                ))
-            })?;
+        endpoints::admin_project_hash_revoke(
+            state,
+            ic::caller(),
+            time_secs(),
+            project,
+            hash,
+            reason,
+        )
+    })
+}
+
+/// Returns the deployment status of the canisters of a project.
+/// The module hash installed in each canister is compared with the approved hashes of the project.
+/// The status of the canisters deployed on another network than the upgrader canister is `Unknown`.
+#[update]
+pub async fn project_deployment_status(key: String) -> Result<ProjectDeploymentStatus> {
+    let (project, latest_approved_hash, network) = STATE.with(|state| {
+        endpoints::project_deployment_status_inspect(&state.permissions.borrow(), &ic::caller())?;
+        let projects = state.projects.borrow();
+        let project = projects
+            .get(&key)
+            .ok_or_else(|| UpgraderError::ProjectNotFound(key.clone()))?;
+        let latest_approved_hash = projects.latest_approved_hash(&key);
+        let network = state.settings.borrow().network();
+        Ok::<_, UpgraderError>((project, latest_approved_hash, network))
+    })?;
+
+    let mut canisters = vec![];
+    for canister in project.canisters.unwrap_or_default() {
+        if canister.network != network {
+            let status = DeploymentStatus::Unknown(format!(
+                "The canister is deployed on the network {}",
+                canister.network
+            ));
+            canisters.push(CanisterDeploymentStatus {
+                canister,
+                module_hash: None,
+                status,
+            });
+            continue;
         }
 
-        Ok(state.polls.borrow_mut().insert(poll))
+        let request = CanisterInfoRequest {
+            canister_id: canister.canister_id,
+            num_requested_changes: None,
+        };
+
+        let status = match canister_info(request).await {
+            Ok((info,)) => module_hash_from_bytes(info.module_hash),
+            Err((code, message)) => Err(format!("{code:?} - {message}")),
+        };
+
+        let (module_hash, status) = match status {
+            Ok(module_hash) => {
+                let status = STATE.with(|state| {
+                    state
+                        .projects
+                        .borrow()
+                        .deployment_status(&key, module_hash.as_ref())
+                });
+                (module_hash.map(|hash| hash.to_string()), status)
+            }
+            Err(reason) => (None, DeploymentStatus::Unknown(reason)),
+        };
+
+        canisters.push(CanisterDeploymentStatus {
+            canister,
+            module_hash,
+            status,
+        });
+    }
+
+    Ok(ProjectDeploymentStatus {
+        project: key,
+        latest_approved_hash,
+        canisters,
     })
 }
 
-/// Inspects permissions for the poll_vote method
-pub fn poll_vote_inspect<M: Memory>(
-    permissions: &Permissions<M>,
-    caller: &Principal,
-) -> Result<()> {
-    permissions.check_has_all_permissions(caller, &[Permission::VotePoll])
+/// Returns up to `limit` alerts of unapproved upgrades starting from the `start` index
+#[query]
+pub fn alerts_get(start: u64, limit: u64) -> BTreeMap<u64, UnapprovedUpgradeAlert> {
+    STATE.with(|state| state.alerts.borrow().page(start, limit))
+}
+
+/// Returns the number of recorded alerts of unapproved upgrades
+#[query]
+pub fn alerts_len() -> u64 {
+    STATE.with(|state| state.alerts.borrow().len())
+}
+
+/// Checks the history of the canisters of the projects and records an alert
+/// for each installed module hash that was never approved.
+/// Only the canisters deployed on the network of the upgrader canister are checked.
+async fn check_unapproved_upgrades() {
+    let canisters = STATE.with(|state| {
+        let network = state.settings.borrow().network();
+        state
+            .projects
+            .borrow()
+            .all()
+            .into_iter()
+            .flat_map(|project| {
+                let key = project.key;
+                project
+                    .canisters
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(move |canister| (key.clone(), canister))
+            })
+            .filter(|(_, canister)| canister.network == network)
+            .collect::<Vec<_>>()
+    });
+
+    for (project, canister) in canisters {
+        let request = CanisterInfoRequest {
+            canister_id: canister.canister_id,
+            num_requested_changes: Some(CANISTER_INFO_MAX_CHANGES),
+        };
+
+        let info = match canister_info(request).await {
+            Ok((info,)) => info,
+            Err((code, message)) => {
+                error!(
+                    "Failed to get the history of the canister {}: {code:?} - {message}",
+                    canister.canister_id
+                );
+                continue;
+            }
+        };
+
+        let deployments = info
+            .recent_changes
+            .into_iter()
+            .filter_map(|change| match change.details {
+                CanisterChangeDetails::CodeDeployment(record) => {
+                    match <[u8; 32]>::try_from(record.module_hash) {
+                        Ok(bytes) => Some(ModuleDeployment {
+                            canister_version: change.canister_version,
+                            timestamp_nanos: change.timestamp_nanos,
+                            module_hash: WasmHash::from(bytes),
+                        }),
+                        Err(bytes) => {
+                            error!("Unexpected module hash length {}", bytes.len());
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let recorded = STATE.with(|state| {
+            state.alerts.borrow_mut().check_deployments(
+                &state.projects.borrow(),
+                &project,
+                &canister,
+                &deployments,
+                time_secs(),
+            )
+        });
+        if recorded > 0 {
+            warn!(
+                "Found {recorded} unapproved upgrades of the canister {} of the project {project}",
+                canister.canister_id
+            );
+        }
+    }
+}
+
+/// Converts the module hash returned by the management canister
+fn module_hash_from_bytes(
+    module_hash: Option<Vec<u8>>,
+) -> std::result::Result<Option<WasmHash>, String> {
+    module_hash
+        .map(|bytes| {
+            <[u8; 32]>::try_from(bytes)
+                .map(WasmHash::from)
+                .map_err(|bytes| format!("Unexpected module hash length {}", bytes.len()))
+        })
+        .transpose()
+}
+
+/// Creates a new poll and returns the generated poll id
+#[update]
+pub fn poll_create(poll: PollCreateData) -> Result<u64> {
+    let end_timestamp_secs = poll.end_timestamp_secs;
+    let id = STATE.with(|state| endpoints::poll_create(state, ic::caller(), time_secs(), poll))?;
+    schedule_poll_finalization(end_timestamp_secs);
+    Ok(id)
 }
 
 /// Votes for a poll. If the voter has already voted, the previous vote is replaced.
 #[update]
 pub fn poll_vote(poll_id: u64, approved: bool) -> Result<()> {
+    STATE.with(|state| endpoints::poll_vote(state, ic::caller(), time_secs(), poll_id, approved))
+}
+
+/// Finalizes a poll whose end timestamp is passed and returns the closed poll.
+/// Any caller can trigger the finalization without waiting for the poll timer.
+#[update]
+pub fn poll_finalize(id: u64) -> Result<ClosedPoll> {
     STATE.with(|state| {
-        let caller = ic::caller();
-        poll_vote_inspect(&state.permissions.borrow(), &caller)?;
-        state
-            .polls
-            .borrow_mut()
-            .vote(poll_id, caller, approved, time_secs())
+        let previous_interval = state.settings.borrow().poll_timer_interval();
+        let result = endpoints::poll_finalize(state, ic::caller(), time_secs(), id);
+        reset_poll_timer_interval_if_changed(state, previous_interval);
+        result
     })
 }
 
+/// Finalizes the polls whose end timestamp is passed and returns the closed polls.
+/// At most `POLL_FINALIZATION_INSTRUCTIONS_LIMIT` instructions are used,
+/// the remaining polls are finalized by a following call or by the poll timer.
+/// Any caller can trigger the finalization without waiting for the poll timer.
+#[update]
+pub fn polls_finalize_due() -> Result<BTreeMap<u64, ClosedPoll>> {
+    STATE.with(|state| {
+        let previous_interval = state.settings.borrow().poll_timer_interval();
+        let result = endpoints::polls_finalize_due(state, ic::caller(), time_secs(), || {
+            instruction_counter() < POLL_FINALIZATION_INSTRUCTIONS_LIMIT
+        });
+        reset_poll_timer_interval_if_changed(state, previous_interval);
+        result
+    })
+}
+
+/// Moves the closed polls older than the retention window to the archive canister
+async fn archive_closed_polls() {
+    let Some((archive, polls)) = STATE.with(|state| {
+        let archive = state.settings.borrow().archive()?;
+        let polls = state.polls.borrow().closed_to_archive(
+            time_secs(),
+            archive.retention_secs,
+            ARCHIVE_BATCH_SIZE,
+        );
+        (!polls.is_empty()).then_some((archive, polls))
+    }) else {
+        return;
+    };
+
+    let ids = polls.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    let result: ic_exports::ic_cdk::api::call::CallResult<(Result<()>,)> =
+        ic_exports::ic_cdk::call(archive.canister_id, "polls_append", (polls,)).await;
+
+    match result {
+        Ok((Ok(()),)) => {
+            info!(
+                "Moved {} closed polls to the archive canister {}",
+                ids.len(),
+                archive.canister_id
+            );
+            STATE.with(|state| {
+                state
+                    .polls
+                    .borrow_mut()
+                    .mark_archived(&ids, archive.canister_id)
+            });
+        }
+        Ok((Err(err),)) => error!("The archive canister rejected the closed polls: {err}"),
+        Err((code, message)) => {
+            error!("Failed to call the archive canister: {code:?} - {message}")
+        }
+    }
+}
+
 /// returns the timestamp in seconds
 #[inline]
 pub fn time_secs() -> u64 {
@@ -250,5 +684,145 @@ pub fn time_secs() -> u64 {
     (ic_exports::ic_kit::ic::time() / 1_000_000_000)
 }
 
+/// returns the cycles balance of the canister
+#[inline]
+pub fn cycles_balance() -> u128 {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        0
+    }
+
+    #[cfg(target_family = "wasm")]
+    ic_exports::ic_cdk::api::canister_balance128()
+}
+
+/// returns the number of instructions executed in the current message
+#[inline]
+fn instruction_counter() -> u64 {
+    #[cfg(not(target_family = "wasm"))]
+    {
+        0
+    }
+
+    #[cfg(target_family = "wasm")]
+    ic_exports::ic_cdk::api::instruction_counter()
+}
+
 // Enable Candid export
 ic_exports::ic_cdk::export_candid!();
+
+#[cfg(test)]
+mod test {
+
+    use std::collections::BTreeSet;
+    use std::path::{Path, PathBuf};
+
+    use candid::types::subtype::{subtype, Gamma};
+    use candid_parser::utils::{service_equal, CandidSource};
+
+    use super::*;
+
+    /// Returns the path of a candid file in the `candid` directory of the crate
+    fn candid_path(file_name: &str) -> PathBuf {
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("candid")
+            .join(file_name)
+    }
+
+    #[test]
+    fn test_candid_interface_is_up_to_date() {
+        // Arrange
+        let generated = __export_service();
+        let path = candid_path("upgrader_canister.did");
+        if std::env::var("UPDATE_CANDID").is_ok() {
+            std::fs::write(&path, &generated).unwrap();
+        }
+
+        // Act
+        let result = service_equal(CandidSource::Text(&generated), CandidSource::File(&path));
+
+        // Assert
+        assert!(
+            result.is_ok(),
+            "{} differs from the canister interface, regenerate it with `UPDATE_CANDID=1 cargo test -p upgrader_canister test_candid_interface_is_up_to_date`: {result:?}",
+            path.display()
+        );
+    }
+
+    /// The methods of the released interface that intentionally break its clients,
+    /// grouped by the reason of the break. These clients must be updated together with the
+    /// canister. The list is emptied when the released interface is replaced.
+    ///
+    /// The methods returning an `opt` stay compatible by the special subtyping rule of the
+    /// optional values: a released client decodes a value it does not know, such as a poll
+    /// of a new type or an archived poll returned by `poll_get`, as `null`.
+    const ACKNOWLEDGED_BREAKING_CHANGES: &[(&str, &[&str])] = &[
+        (
+            "UpgraderError variants added for the multi-admin approval \
+            and to report the missing polls, projects and permissions and the revoked hashes",
+            &[
+                "admin_disable_inspect_message",
+                "admin_permissions_add",
+                "admin_permissions_get",
+                "admin_permissions_remove",
+                "caller_permissions_get",
+                "poll_create",
+                "poll_vote",
+                "project_create",
+            ],
+        ),
+        (
+            "PollType variants added for the settings, project, revocation, re-approval \
+            and batch polls and PollResult variant added for the polls whose action failed",
+            &["poll_get_all_closed", "poll_get_all_pending"],
+        ),
+    ];
+
+    /// Returns the names of the released methods whose current type
+    /// is missing or is not a subtype of the released one
+    fn incompatible_released_methods(generated: &str, released: &Path) -> BTreeSet<String> {
+        let (mut env, current) = CandidSource::Text(generated).load().unwrap();
+        let (released_env, released) = CandidSource::File(released).load().unwrap();
+        let released = env.merge_type(released_env, released.unwrap());
+        let current_methods = env.as_service(&current.unwrap()).unwrap().to_vec();
+        let released_methods = env.as_service(&released).unwrap().to_vec();
+
+        released_methods
+            .into_iter()
+            .filter(|(name, released_type)| {
+                match current_methods
+                    .iter()
+                    .find(|(current_name, _)| current_name == name)
+                {
+                    Some((_, current_type)) => {
+                        subtype(&mut Gamma::new(), &env, current_type, released_type).is_err()
+                    }
+                    None => true,
+                }
+            })
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_candid_interface_is_compatible_with_released() {
+        // Arrange
+        let generated = __export_service();
+        let released = candid_path("upgrader_canister.released.did");
+        let acknowledged = ACKNOWLEDGED_BREAKING_CHANGES
+            .iter()
+            .flat_map(|(_, methods)| methods.iter().map(|method| method.to_string()))
+            .collect::<BTreeSet<_>>();
+
+        // Act
+        let incompatible = incompatible_released_methods(&generated, &released);
+
+        // Assert
+        assert_eq!(
+            incompatible,
+            acknowledged,
+            "the methods of the canister interface that are not compatible with the released one in {} differ from ACKNOWLEDGED_BREAKING_CHANGES",
+            released.display()
+        );
+    }
+}
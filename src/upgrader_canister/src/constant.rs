@@ -6,6 +6,75 @@ pub(crate) const POLLS_PENDING_MAP_MEMORY_ID: u8 = 3;
 pub(crate) const POLLS_CLOSED_MAP_MEMORY_ID: u8 = 4;
 pub(crate) const POLLS_ID_SEQUENCE_MEMORY_ID: u8 = 5;
 pub(crate) const SETTINGS_MAP_MEMORY_ID: u8 = 6;
+pub(crate) const ADMIN_ACTIONS_MAP_MEMORY_ID: u8 = 7;
+pub(crate) const ADMIN_ACTIONS_ID_SEQUENCE_MEMORY_ID: u8 = 8;
+pub(crate) const AUDIT_LOG_INDEX_MEMORY_ID: u8 = 9;
+pub(crate) const AUDIT_LOG_DATA_MEMORY_ID: u8 = 10;
+pub(crate) const POLLS_ARCHIVED_INDEX_MEMORY_ID: u8 = 11;
+pub(crate) const PROJECT_HASHES_MAP_MEMORY_ID: u8 = 12;
+pub(crate) const ALERTS_LOG_INDEX_MEMORY_ID: u8 = 13;
+pub(crate) const ALERTS_LOG_DATA_MEMORY_ID: u8 = 14;
+pub(crate) const ALERTS_CHECKED_VERSIONS_MAP_MEMORY_ID: u8 = 15;
 
-/// The interval at which the poll timer should run
+/// The names of the stable memories used by the canister, reported by the metrics
+pub(crate) const MEMORY_IDS: [(&str, u8); 15] = [
+    ("permissions_map", PERMISSIONS_MAP_MEMORY_ID),
+    ("projects_map", PROJECTS_MAP_MEMORY_ID),
+    ("polls_pending_map", POLLS_PENDING_MAP_MEMORY_ID),
+    ("polls_closed_map", POLLS_CLOSED_MAP_MEMORY_ID),
+    ("polls_id_sequence", POLLS_ID_SEQUENCE_MEMORY_ID),
+    ("settings_map", SETTINGS_MAP_MEMORY_ID),
+    ("admin_actions_map", ADMIN_ACTIONS_MAP_MEMORY_ID),
+    (
+        "admin_actions_id_sequence",
+        ADMIN_ACTIONS_ID_SEQUENCE_MEMORY_ID,
+    ),
+    ("audit_log_index", AUDIT_LOG_INDEX_MEMORY_ID),
+    ("audit_log_data", AUDIT_LOG_DATA_MEMORY_ID),
+    ("polls_archived_index", POLLS_ARCHIVED_INDEX_MEMORY_ID),
+    ("project_hashes_map", PROJECT_HASHES_MAP_MEMORY_ID),
+    ("alerts_log_index", ALERTS_LOG_INDEX_MEMORY_ID),
+    ("alerts_log_data", ALERTS_LOG_DATA_MEMORY_ID),
+    (
+        "alerts_checked_versions_map",
+        ALERTS_CHECKED_VERSIONS_MAP_MEMORY_ID,
+    ),
+];
+
+/// The default interval at which the poll timer should run
 pub const POLL_TIMER_INTERVAL: Duration = Duration::from_secs(600);
+
+/// The maximum delay of the one-shot timer scheduled to finalize a poll at its end time
+pub const POLL_FINALIZATION_TIMER_MAX_DELAY: Duration = Duration::from_secs(365 * 24 * 3600);
+
+/// The maximum number of instructions used to finalize polls in a single message.
+/// The remaining polls are finalized in a following message.
+pub const POLL_FINALIZATION_INSTRUCTIONS_LIMIT: u64 = 10_000_000_000;
+
+/// The interval at which the closed polls are moved to the archive canister
+pub const ARCHIVE_TIMER_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// The maximum number of closed polls moved to the archive canister in a single call
+pub const ARCHIVE_BATCH_SIZE: usize = 100;
+
+/// The network of the upgrader canister if it is not set in the init data
+pub const DEFAULT_NETWORK: &str = "ic";
+
+/// The interval at which the canisters of the projects are checked for unapproved upgrades
+pub const ALERTS_TIMER_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// The maximum number of changes requested from the history of a canister.
+/// It is the maximum number of changes returned by the management canister.
+pub const CANISTER_INFO_MAX_CHANGES: u64 = 20;
+
+/// The maximum number of closed polls returned by a single request to the dashboard
+pub const CLOSED_POLLS_MAX_PAGE_SIZE: u64 = 100;
+
+/// The maximum number of alerts returned by a single query
+pub const ALERTS_MAX_PAGE_SIZE: u64 = 100;
+
+/// The maximum number of audit log entries returned by a single query
+pub const AUDIT_LOG_MAX_PAGE_SIZE: u64 = 100;
+
+/// The maximum length of the arguments summary stored in an audit log entry
+pub const AUDIT_LOG_ARGS_MAX_LENGTH: usize = 512;
@@ -0,0 +1,823 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use candid::Principal;
+use ic_stable_structures::stable_structures::Memory;
+use upgrader_canister_did::error::Result;
+use upgrader_canister_did::{
+    AdminAction, AdminApprovalSettings, ArchiveSettings, AuditLogEntry, AuditLogOutcome,
+    ClosedPoll, PendingAdminAction, Permission, PermissionList, PollAction, PollCreateData,
+    PollType, ProjectData, UpgraderError, WasmHash,
+};
+
+use crate::state::permission::Permissions;
+use crate::state::UpgraderCanisterState;
+
+// The logic of the canister endpoints, run over the canister state with an explicit
+// caller and time, so that the in-memory mock of the client runs the same code as the canister.
+// The timers and the inter-canister calls are left to the canister.
+
+/// Returns the permissions of a principal
+pub fn admin_permissions_get(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    principal: Principal,
+) -> Result<PermissionList> {
+    let permissions = state.permissions.borrow();
+    permissions.check_admin(&caller)?;
+    Ok(permissions.get_permissions(&principal))
+}
+
+/// Adds permissions to a principal and returns the principal permissions.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_permissions_add(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    principal: Principal,
+    permissions: Vec<Permission>,
+) -> Result<PermissionList> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_permissions_add",
+        format!("principal: {principal}, permissions: {permissions:?}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            require_admin_approval(
+                state,
+                caller,
+                now_secs,
+                AdminAction::PermissionsAdd {
+                    principal,
+                    permissions: permissions.clone(),
+                },
+            )?;
+            state
+                .permissions
+                .borrow_mut()
+                .add_permissions(principal, permissions)
+        },
+    )
+}
+
+/// Removes permissions from a principal and returns the principal permissions.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_permissions_remove(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    principal: Principal,
+    permissions: Vec<Permission>,
+) -> Result<PermissionList> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_permissions_remove",
+        format!("principal: {principal}, permissions: {permissions:?}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            check_admins_removal(state, &[principal], &permissions)?;
+            require_admin_approval(
+                state,
+                caller,
+                now_secs,
+                AdminAction::PermissionsRemove {
+                    principal,
+                    permissions: permissions.clone(),
+                },
+            )?;
+            state
+                .permissions
+                .borrow_mut()
+                .remove_permissions(principal, &permissions)
+        },
+    )
+}
+
+/// Disable/Enable the inspect message.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_disable_inspect_message(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    value: bool,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_disable_inspect_message",
+        format!("value: {value}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            require_admin_approval(
+                state,
+                caller,
+                now_secs,
+                AdminAction::DisableInspectMessage(value),
+            )?;
+            state.settings.borrow_mut().disable_inspect_message(value);
+            Ok(())
+        },
+    )
+}
+
+/// Sets the multi-admin approval settings. Passing `None` disables the multi-admin approval.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_approval_settings_set(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    approval: Option<AdminApprovalSettings>,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_approval_settings_set",
+        format!("approval: {approval:?}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            let admins_count = admins_count(state);
+            state
+                .settings
+                .borrow()
+                .check_admin_approval(approval.as_ref(), admins_count)?;
+            require_admin_approval(
+                state,
+                caller,
+                now_secs,
+                AdminAction::SetAdminApproval(approval),
+            )?;
+            apply_admin_action(state, AdminAction::SetAdminApproval(approval))
+        },
+    )
+}
+
+/// Sets the archive settings. Passing `None` disables the archiving of the closed polls.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_archive_settings_set(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    archive: Option<ArchiveSettings>,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_archive_settings_set",
+        format!("archive: {archive:?}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            require_admin_approval(state, caller, now_secs, AdminAction::SetArchive(archive))?;
+            state.settings.borrow_mut().set_archive(archive);
+            Ok(())
+        },
+    )
+}
+
+/// Sets the interval in seconds at which the poll timer finalizes the polls.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_poll_timer_interval_set(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    interval_secs: u64,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_poll_timer_interval_set",
+        format!("interval_secs: {interval_secs}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            require_admin_approval(
+                state,
+                caller,
+                now_secs,
+                AdminAction::SetPollTimerInterval(interval_secs),
+            )?;
+            apply_admin_action(state, AdminAction::SetPollTimerInterval(interval_secs))
+        },
+    )
+}
+
+/// Returns all the pending admin actions that are not expired
+pub fn admin_action_get_all_pending(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+) -> Result<BTreeMap<u64, PendingAdminAction>> {
+    state.permissions.borrow().check_admin(&caller)?;
+    Ok(state.admin_actions.borrow().all_pending(now_secs))
+}
+
+/// Approves a pending admin action.
+/// The action is executed as soon as the required number of distinct admins approved it.
+/// Returns true if the action was executed.
+pub fn admin_action_approve(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    id: u64,
+) -> Result<bool> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_action_approve",
+        format!("id: {id}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+
+            let action = state
+                .admin_actions
+                .borrow_mut()
+                .approve(id, caller, now_secs)?;
+
+            // Only the approvals of principals that are still admins are counted
+            let approvals = {
+                let permissions = state.permissions.borrow();
+                action
+                    .approvals
+                    .iter()
+                    .filter(|principal| permissions.check_admin(principal).is_ok())
+                    .count() as u32
+            };
+            let threshold = state
+                .settings
+                .borrow()
+                .admin_approval()
+                .map(|approval| approval.threshold)
+                .unwrap_or(1);
+
+            if approvals < threshold {
+                return Ok(false);
+            }
+
+            // The action stays pending if it cannot be applied
+            apply_admin_action(state, action.action)?;
+            state.admin_actions.borrow_mut().remove(&id);
+            Ok(true)
+        },
+    )
+}
+
+/// Returns the audit log entries starting from the `start` index.
+/// At most `AUDIT_LOG_MAX_PAGE_SIZE` entries are returned.
+/// The entries expose the arguments and the errors of the calls, so only the admins can read them.
+pub fn audit_log_get(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    start: u64,
+    limit: u64,
+) -> Result<BTreeMap<u64, AuditLogEntry>> {
+    state.permissions.borrow().check_admin(&caller)?;
+    Ok(state.audit_log.borrow().page(start, limit))
+}
+
+/// Returns the number of entries in the audit log
+pub fn audit_log_len(state: &UpgraderCanisterState, caller: Principal) -> Result<u64> {
+    state.permissions.borrow().check_admin(&caller)?;
+    Ok(state.audit_log.borrow().len())
+}
+
+/// Executes a state-changing call and records it in the audit log.
+/// The successful calls are always recorded, while the failed calls are recorded only for
+/// the principals holding at least one permission, so that the other principals
+/// cannot grow the log without bound.
+fn audited<T>(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    method: &str,
+    args: String,
+    f: impl FnOnce(&UpgraderCanisterState) -> Result<T>,
+) -> Result<T> {
+    let is_authorized = !state
+        .permissions
+        .borrow()
+        .get_permissions(&caller)
+        .permissions
+        .is_empty();
+
+    let result = f(state);
+    if result.is_err() && !is_authorized {
+        return result;
+    }
+
+    let outcome = match &result {
+        Ok(_) => AuditLogOutcome::Success,
+        Err(err) => AuditLogOutcome::Failure(err.clone()),
+    };
+    state.audit_log.borrow_mut().append(AuditLogEntry {
+        caller,
+        method: method.to_string(),
+        args,
+        timestamp_secs: now_secs,
+        outcome,
+    });
+    result
+}
+
+/// Returns an error with the id of a new pending admin action
+/// if the multi-admin approval is enabled and requires more than one admin
+fn require_admin_approval(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    action: AdminAction,
+) -> Result<()> {
+    let approval = state.settings.borrow().admin_approval();
+    match approval {
+        Some(approval) if approval.threshold > 1 => {
+            let id = state.admin_actions.borrow_mut().propose(
+                action,
+                caller,
+                now_secs,
+                approval.expiry_secs,
+            );
+            Err(UpgraderError::AdminApprovalRequired(id))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Executes an approved admin action.
+/// The canister resets the poll timer if the action changed its interval.
+fn apply_admin_action(state: &UpgraderCanisterState, action: AdminAction) -> Result<()> {
+    match action {
+        AdminAction::PermissionsAdd {
+            principal,
+            permissions,
+        } => {
+            state
+                .permissions
+                .borrow_mut()
+                .add_permissions(principal, permissions)?;
+        }
+        AdminAction::PermissionsRemove {
+            principal,
+            permissions,
+        } => {
+            check_admins_removal(state, &[principal], &permissions)?;
+            state
+                .permissions
+                .borrow_mut()
+                .remove_permissions(principal, &permissions)?;
+        }
+        AdminAction::DisableInspectMessage(value) => {
+            state.settings.borrow_mut().disable_inspect_message(value);
+        }
+        AdminAction::SetAdminApproval(approval) => {
+            let admins_count = admins_count(state);
+            state
+                .settings
+                .borrow_mut()
+                .set_admin_approval(approval, admins_count)?;
+        }
+        AdminAction::SetArchive(archive) => {
+            state.settings.borrow_mut().set_archive(archive);
+        }
+        AdminAction::SetPollTimerInterval(interval_secs) => {
+            state
+                .settings
+                .borrow_mut()
+                .set_poll_timer_interval(Duration::from_secs(interval_secs))?;
+        }
+        AdminAction::RevokeProjectHash {
+            project,
+            hash,
+            reason,
+        } => {
+            state
+                .projects
+                .borrow_mut()
+                .revoke_hash(&project, &hash, None, reason)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the number of admins
+fn admins_count(state: &UpgraderCanisterState) -> u64 {
+    state
+        .permissions
+        .borrow()
+        .principals_count(&Permission::Admin)
+}
+
+/// Returns an error if removing the permissions from the principals leaves fewer admins
+/// than the threshold of the multi-admin approval
+fn check_admins_removal(
+    state: &UpgraderCanisterState,
+    principals: &[Principal],
+    permissions: &[Permission],
+) -> Result<()> {
+    let admins_count = state
+        .permissions
+        .borrow()
+        .admins_count_after_removal(principals, permissions);
+    state.settings.borrow().check_admins_count(admins_count)
+}
+
+/// Inspects permissions for the project_create method
+pub fn project_create_inspect<M: Memory>(
+    permissions: &Permissions<M>,
+    caller: &Principal,
+) -> Result<()> {
+    permissions.check_has_all_permissions(caller, &[Permission::CreateProject])
+}
+
+/// Creates a new project
+pub fn project_create(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    project: ProjectData,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "project_create",
+        format!("project: {project:?}"),
+        |state| {
+            project_create_inspect(&state.permissions.borrow(), &caller)?;
+            check_project_key_available(state, &project.key)?;
+            state.projects.borrow_mut().insert(project)
+        },
+    )
+}
+
+/// Revokes an approved project hash without waiting for a poll.
+/// It is meant for emergencies, like a vulnerability found in the approved wasm.
+/// If the multi-admin approval is enabled, a pending admin action is created instead.
+pub fn admin_project_hash_revoke(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    project: String,
+    hash: String,
+    reason: String,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "admin_project_hash_revoke",
+        format!("project: {project}, hash: {hash}, reason: {reason}"),
+        |state| {
+            state.permissions.borrow().check_admin(&caller)?;
+            let hash = WasmHash::normalize(&hash)?;
+            state
+                .projects
+                .borrow()
+                .check_hash_approved(&project, &hash)?;
+            require_admin_approval(
+                state,
+                caller,
+                now_secs,
+                AdminAction::RevokeProjectHash {
+                    project: project.clone(),
+                    hash: hash.clone(),
+                    reason: reason.clone(),
+                },
+            )?;
+            state
+                .projects
+                .borrow_mut()
+                .revoke_hash(&project, &hash, None, reason)
+        },
+    )
+}
+
+/// Inspects permissions for the project_deployment_status method.
+/// The method calls the management canister for each canister of the project,
+/// so it is reserved to the principals with any permission.
+pub fn project_deployment_status_inspect<M: Memory>(
+    permissions: &Permissions<M>,
+    caller: &Principal,
+) -> Result<()> {
+    permissions.check_has_any_permission(
+        caller,
+        &[
+            Permission::Admin,
+            Permission::CreateProject,
+            Permission::CreatePoll,
+            Permission::VotePoll,
+        ],
+    )
+}
+
+/// Inspects permissions for the poll_create method
+pub fn poll_create_inspect<M: Memory>(
+    permissions: &Permissions<M>,
+    caller: &Principal,
+) -> Result<()> {
+    permissions.check_has_all_permissions(caller, &[Permission::CreatePoll])
+}
+
+/// Creates a new poll and returns the generated poll id
+pub fn poll_create(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    poll: PollCreateData,
+) -> Result<u64> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "poll_create",
+        format!("poll: {poll:?}"),
+        |state| {
+            poll_create_inspect(&state.permissions.borrow(), &caller)?;
+
+            let mut poll = poll;
+            check_poll_create(state, &mut poll)?;
+            Ok(state.polls.borrow_mut().insert(poll))
+        },
+    )
+}
+
+/// Validates a new poll against the canister state and normalizes its wasm hashes
+fn check_poll_create(state: &UpgraderCanisterState, poll: &mut PollCreateData) -> Result<()> {
+    normalize_poll_type_hashes(&mut poll.poll_type)?;
+    validate_poll_type(state, &poll.poll_type)?;
+    check_no_duplicated_pending_poll(state, &poll.poll_type)?;
+    state
+        .settings
+        .borrow()
+        .check_poll_duration(poll.start_timestamp_secs, poll.end_timestamp_secs)
+}
+
+/// Normalizes the wasm hashes of the poll type to the canonical lowercase hex format.
+/// Returns an error if a hash is malformed.
+fn normalize_poll_type_hashes(poll_type: &mut PollType) -> Result<()> {
+    match poll_type {
+        PollType::ProjectHash { hash, .. }
+        | PollType::RevokeProjectHash { hash, .. }
+        | PollType::ReapproveProjectHash { hash, .. } => {
+            *hash = WasmHash::normalize(hash)?;
+        }
+        PollType::Batch(actions) => {
+            for action in actions {
+                if let PollAction::ProjectHash { hash, .. } = action {
+                    *hash = WasmHash::normalize(hash)?;
+                }
+            }
+        }
+        PollType::AddPermission { .. }
+        | PollType::RemovePermission { .. }
+        | PollType::UpdateSettings(_)
+        | PollType::CreateProject(_)
+        | PollType::UpdateProject(_) => (),
+    }
+    Ok(())
+}
+
+/// Returns an error if a pending poll already approves or revokes
+/// the same hash of the same project
+fn check_no_duplicated_pending_poll(
+    state: &UpgraderCanisterState,
+    poll_type: &PollType,
+) -> Result<()> {
+    let approvals = project_hash_approvals(poll_type);
+    let is_duplicated =
+        state
+            .polls
+            .borrow()
+            .any_pending(|pending| match (&pending.poll_type, poll_type) {
+                (
+                    PollType::RevokeProjectHash {
+                        project: pending_project,
+                        hash: pending_hash,
+                        ..
+                    },
+                    PollType::RevokeProjectHash { project, hash, .. },
+                ) => pending_project == project && pending_hash == hash,
+                (pending_poll_type, _) => project_hash_approvals(pending_poll_type)
+                    .iter()
+                    .any(|approval| approvals.contains(approval)),
+            });
+
+    if is_duplicated {
+        return Err(UpgraderError::BadRequest(
+            "Cannot create poll, a pending poll for the same project hash already exists"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Returns the (project, hash) pairs approved by the poll type
+fn project_hash_approvals(poll_type: &PollType) -> Vec<(&String, &String)> {
+    match poll_type {
+        PollType::ProjectHash { project, hash }
+        | PollType::ReapproveProjectHash { project, hash } => vec![(project, hash)],
+        PollType::Batch(actions) => actions
+            .iter()
+            .filter_map(|action| match action {
+                PollAction::ProjectHash { project, hash } => Some((project, hash)),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Validates the poll type before creating the poll
+fn validate_poll_type(state: &UpgraderCanisterState, poll_type: &PollType) -> Result<()> {
+    match poll_type {
+        PollType::ProjectHash { project, hash } => check_hash_approvable(state, project, hash),
+        PollType::AddPermission { principals, .. }
+        | PollType::RemovePermission { principals, .. } => check_principals(state, principals),
+        PollType::UpdateSettings(patch) => state.settings.borrow().check_patch(patch),
+        PollType::CreateProject(project) => check_project_key_available(state, &project.key),
+        PollType::UpdateProject(project) => check_project_exists(state, &project.key),
+        PollType::RevokeProjectHash { project, hash, .. } => {
+            state.projects.borrow().check_hash_approved(project, hash)
+        }
+        PollType::ReapproveProjectHash { project, hash } => {
+            state.projects.borrow().check_hash_revoked(project, hash)
+        }
+        PollType::Batch(actions) => {
+            if actions.is_empty() {
+                return Err(UpgraderError::BadRequest(
+                    "Cannot create poll, the batch has no actions".to_string(),
+                ));
+            }
+
+            for action in actions {
+                match action {
+                    PollAction::ProjectHash { project, hash } => {
+                        check_hash_approvable(state, project, hash)?
+                    }
+                    PollAction::AddPermission { principals, .. }
+                    | PollAction::RemovePermission { principals, .. } => {
+                        check_principals(state, principals)?
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Returns an error if one of the principals is anonymous
+fn check_principals(state: &UpgraderCanisterState, principals: &[Principal]) -> Result<()> {
+    let permissions = state.permissions.borrow();
+    for principal in principals {
+        permissions.check_anonymous_principal(principal)?;
+    }
+    Ok(())
+}
+
+/// Returns an error if a project with the key exists or a pending poll is creating it
+fn check_project_key_available(state: &UpgraderCanisterState, key: &str) -> Result<()> {
+    let is_key_used = state.projects.borrow().get(&key.to_string()).is_some()
+        || state
+            .polls
+            .borrow()
+            .any_pending(|poll| match &poll.poll_type {
+                PollType::CreateProject(pending) => pending.key == key,
+                _ => false,
+            });
+    if is_key_used {
+        return Err(UpgraderError::NotUniqueKey(key.to_string()));
+    }
+    Ok(())
+}
+
+/// Returns an error if the project does not exist or if the hash was revoked:
+/// a revoked hash can be approved again only by a `ReapproveProjectHash` poll
+fn check_hash_approvable(
+    state: &UpgraderCanisterState,
+    project: &String,
+    hash: &str,
+) -> Result<()> {
+    check_project_exists(state, project)?;
+    state
+        .projects
+        .borrow()
+        .check_hash_not_revoked(project, hash)
+}
+
+fn check_project_exists(state: &UpgraderCanisterState, project: &String) -> Result<()> {
+    state
+        .projects
+        .borrow()
+        .get(project)
+        .ok_or_else(|| UpgraderError::ProjectNotFound(project.clone()))?;
+    Ok(())
+}
+
+/// Inspects permissions for the poll_vote method
+pub fn poll_vote_inspect<M: Memory>(
+    permissions: &Permissions<M>,
+    caller: &Principal,
+) -> Result<()> {
+    permissions.check_has_all_permissions(caller, &[Permission::VotePoll])
+}
+
+/// Votes for a poll. If the voter has already voted, the previous vote is replaced.
+pub fn poll_vote(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    poll_id: u64,
+    approved: bool,
+) -> Result<()> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "poll_vote",
+        format!("poll_id: {poll_id}, approved: {approved}"),
+        |state| {
+            poll_vote_inspect(&state.permissions.borrow(), &caller)?;
+            state
+                .polls
+                .borrow_mut()
+                .vote(poll_id, caller, approved, now_secs)
+        },
+    )
+}
+
+/// Finalizes a poll whose end timestamp is passed and returns the closed poll.
+/// Any caller can trigger the finalization without waiting for the poll timer.
+pub fn poll_finalize(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    id: u64,
+) -> Result<ClosedPoll> {
+    audited(
+        state,
+        caller,
+        now_secs,
+        "poll_finalize",
+        format!("id: {id}"),
+        |state| {
+            let mut permissions = state.permissions.borrow_mut();
+            let mut settings = state.settings.borrow_mut();
+            let mut projects = state.projects.borrow_mut();
+            state.polls.borrow_mut().finalize_poll(
+                id,
+                now_secs,
+                &mut permissions,
+                &mut settings,
+                &mut projects,
+            )
+        },
+    )
+}
+
+/// Finalizes the polls whose end timestamp is passed and returns the closed polls.
+/// The finalization stops as soon as `has_budget` returns false.
+/// Any caller can trigger the finalization without waiting for the poll timer.
+/// A call finalizing no poll does not change the state, so it is not recorded in the audit log.
+pub fn polls_finalize_due(
+    state: &UpgraderCanisterState,
+    caller: Principal,
+    now_secs: u64,
+    has_budget: impl FnMut() -> bool,
+) -> Result<BTreeMap<u64, ClosedPoll>> {
+    if !state.polls.borrow().has_polls_to_finalize(now_secs) {
+        return Ok(BTreeMap::new());
+    }
+
+    audited(
+        state,
+        caller,
+        now_secs,
+        "polls_finalize_due",
+        String::new(),
+        |state| Ok(finalize_due_polls(state, now_secs, has_budget)),
+    )
+}
+
+/// Finalizes the polls whose end timestamp is passed while `has_budget` returns true.
+/// It is run by the poll timers, so the call is not recorded in the audit log.
+pub fn finalize_due_polls(
+    state: &UpgraderCanisterState,
+    now_secs: u64,
+    has_budget: impl FnMut() -> bool,
+) -> BTreeMap<u64, ClosedPoll> {
+    let mut permissions = state.permissions.borrow_mut();
+    let mut settings = state.settings.borrow_mut();
+    let mut projects = state.projects.borrow_mut();
+    state.polls.borrow_mut().finalize_polls(
+        now_secs,
+        &mut permissions,
+        &mut settings,
+        &mut projects,
+        has_budget,
+    )
+}
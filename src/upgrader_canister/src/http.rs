@@ -0,0 +1,391 @@
+use serde::Serialize;
+use upgrader_canister_did::{HttpRequest, HttpResponse, PendingPoll, PollAction, PollType};
+
+use crate::constant::CLOSED_POLLS_MAX_PAGE_SIZE;
+use crate::state::UpgraderCanisterState;
+
+/// Serves the read-only governance dashboard.
+///
+/// Available paths:
+/// - `/`: an HTML overview of the pending polls and projects
+/// - `/polls/pending`: the pending polls as JSON
+/// - `/polls/closed`: the closed polls as JSON, paginated with the `start` poll id
+///   and the `limit` query parameters
+/// - `/projects`: the projects as JSON
+/// - `/build`: the canister build data as JSON
+/// - `/metrics`: the canister metrics in the Prometheus text format
+pub fn http_request(state: &UpgraderCanisterState, request: &HttpRequest) -> HttpResponse {
+    if !request.method.eq_ignore_ascii_case("GET") {
+        return text_response(405, "Method not allowed");
+    }
+
+    match request_path(&request.url) {
+        "/" | "/index.html" => html_response(200, render_overview(state)),
+        "/polls/pending" => json_response(&state.polls.borrow().all_pending()),
+        "/polls/closed" => match (
+            query_param(&request.url, "start"),
+            query_param(&request.url, "limit"),
+        ) {
+            (Ok(start), Ok(limit)) => json_response(&state.polls.borrow().closed_page(
+                start.unwrap_or_default(),
+                limit.unwrap_or(CLOSED_POLLS_MAX_PAGE_SIZE),
+            )),
+            (Err(err), _) | (_, Err(err)) => text_response(400, &err),
+        },
+        "/projects" => json_response(&state.projects.borrow().all()),
+        "/build" => json_response(&crate::build_data::canister_build_data()),
+        "/metrics" => response(
+            200,
+            "text/plain; version=0.0.4; charset=utf-8",
+            crate::metrics::render_metrics(state, crate::canister::cycles_balance()).into_bytes(),
+        ),
+        _ => text_response(404, "Not found"),
+    }
+}
+
+/// Returns the path of the url without the query string and the trailing slash
+fn request_path(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    match path.trim_end_matches('/') {
+        "" => "/",
+        path => path,
+    }
+}
+
+/// Returns the numeric value of a parameter of the query string of the url
+fn query_param(url: &str, name: &str) -> Result<Option<u64>, String> {
+    let query = url
+        .split('#')
+        .next()
+        .and_then(|url| url.split_once('?'))
+        .map(|(_, query)| query)
+        .unwrap_or_default();
+
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| {
+            value
+                .parse()
+                .map_err(|_| format!("Invalid query parameter {name}: {value}"))
+        })
+        .transpose()
+}
+
+fn json_response<T: Serialize>(value: &T) -> HttpResponse {
+    match serde_json::to_vec(value) {
+        Ok(body) => response(200, "application/json", body),
+        Err(err) => text_response(500, &format!("Failed to serialize the response: {err}")),
+    }
+}
+
+fn html_response(status_code: u16, body: String) -> HttpResponse {
+    response(status_code, "text/html; charset=utf-8", body.into_bytes())
+}
+
+fn text_response(status_code: u16, body: &str) -> HttpResponse {
+    response(
+        status_code,
+        "text/plain; charset=utf-8",
+        body.as_bytes().to_vec(),
+    )
+}
+
+fn response(status_code: u16, content_type: &str, body: Vec<u8>) -> HttpResponse {
+    HttpResponse {
+        status_code,
+        headers: vec![
+            ("Content-Type".to_string(), content_type.to_string()),
+            ("Content-Length".to_string(), body.len().to_string()),
+        ],
+        body,
+    }
+}
+
+/// Renders the HTML overview of the pending polls and projects
+fn render_overview(state: &UpgraderCanisterState) -> String {
+    let pending_polls = state.polls.borrow().all_pending();
+    let closed_polls_count = state.polls.borrow().closed_count();
+    let projects = state.projects.borrow().all();
+
+    let mut html = String::from(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>Upgrader canister</title></head><body>",
+    );
+    html.push_str("<h1>Upgrader canister</h1>");
+
+    html.push_str(&format!("<h2>Pending polls ({})</h2>", pending_polls.len()));
+    html.push_str(
+        "<table><tr><th>Id</th><th>Description</th><th>Type</th><th>Yes</th><th>No</th><th>Start</th><th>End</th></tr>",
+    );
+    for (id, poll) in &pending_polls {
+        html.push_str(&render_pending_poll_row(*id, poll));
+    }
+    html.push_str("</table>");
+
+    html.push_str(&format!(
+        "<h2>Closed polls</h2><p>{closed_polls_count} closed polls, see <a href=\"/polls/closed\">/polls/closed</a></p>"
+    ));
+
+    html.push_str(&format!("<h2>Projects ({})</h2>", projects.len()));
+    html.push_str("<table><tr><th>Key</th><th>Name</th><th>Description</th></tr>");
+    for project in &projects {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&project.key),
+            escape_html(&project.name),
+            escape_html(&project.description)
+        ));
+    }
+    html.push_str("</table>");
+
+    html.push_str("</body></html>");
+    html
+}
+
+fn render_pending_poll_row(id: u64, poll: &PendingPoll) -> String {
+    format!(
+        "<tr><td>{id}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+        escape_html(&poll.description),
+        escape_html(&poll_type_summary(&poll.poll_type)),
+        poll.yes_votes(),
+        poll.no_votes(),
+        poll.start_timestamp_secs,
+        poll.end_timestamp_secs
+    )
+}
+
+fn poll_type_summary(poll_type: &PollType) -> String {
+    match poll_type {
+        PollType::ProjectHash { project, hash } => {
+            format!("Project hash: {project} - {hash}")
+        }
+        PollType::AddPermission {
+            principals,
+            permissions,
+        } => format!("Add permissions: {permissions:?} to {principals:?}"),
+        PollType::RemovePermission {
+            principals,
+            permissions,
+        } => format!("Remove permissions: {permissions:?} from {principals:?}"),
+        PollType::UpdateSettings(patch) => format!("Update settings: {patch:?}"),
+        PollType::CreateProject(project) => format!("Create project: {}", project.key),
+        PollType::UpdateProject(project) => format!("Update project: {}", project.key),
+        PollType::RevokeProjectHash {
+            project,
+            hash,
+            reason,
+        } => format!("Revoke project hash: {project} - {hash} ({reason})"),
+        PollType::ReapproveProjectHash { project, hash } => {
+            format!("Reapprove project hash: {project} - {hash}")
+        }
+        PollType::Batch(actions) => format!(
+            "Batch: {}",
+            actions
+                .iter()
+                .map(poll_action_summary)
+                .collect::<Vec<_>>()
+                .join("; ")
+        ),
+    }
+}
+
+fn poll_action_summary(action: &PollAction) -> String {
+    match action {
+        PollAction::ProjectHash { project, hash } => {
+            format!("Project hash: {project} - {hash}")
+        }
+        PollAction::AddPermission {
+            principals,
+            permissions,
+        } => format!("Add permissions: {permissions:?} to {principals:?}"),
+        PollAction::RemovePermission {
+            principals,
+            permissions,
+        } => format!("Remove permissions: {permissions:?} from {principals:?}"),
+    }
+}
+
+/// Escapes the characters that have a special meaning in HTML
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+
+    use std::collections::BTreeMap;
+
+    use upgrader_canister_did::{ClosedPoll, PollCreateData, ProjectData};
+
+    use super::*;
+
+    fn get(url: &str) -> HttpRequest {
+        HttpRequest {
+            method: "GET".to_string(),
+            url: url.to_string(),
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    fn state_with_data() -> UpgraderCanisterState {
+        let state = UpgraderCanisterState::default();
+        state
+            .projects
+            .borrow_mut()
+            .insert(ProjectData {
+                key: "key".to_string(),
+                name: "<b>Project</b>".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+        state.polls.borrow_mut().insert(PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::ProjectHash {
+                project: "key".to_string(),
+                hash: "hash".to_string(),
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 10,
+        });
+        state
+    }
+
+    #[test]
+    fn test_request_path() {
+        assert_eq!(request_path(""), "/");
+        assert_eq!(request_path("/"), "/");
+        assert_eq!(request_path("/projects"), "/projects");
+        assert_eq!(request_path("/projects/"), "/projects");
+        assert_eq!(request_path("/polls/pending?limit=10"), "/polls/pending");
+    }
+
+    #[test]
+    fn test_query_param() {
+        assert_eq!(query_param("/polls/closed", "start"), Ok(None));
+        assert_eq!(query_param("/polls/closed?start=5", "start"), Ok(Some(5)));
+        assert_eq!(
+            query_param("/polls/closed?start=5&limit=10#top", "limit"),
+            Ok(Some(10))
+        );
+        assert_eq!(query_param("/polls/closed?limit=10", "start"), Ok(None));
+        assert!(query_param("/polls/closed?start=five", "start").is_err());
+    }
+
+    #[test]
+    fn test_closed_polls_pagination() {
+        let state = UpgraderCanisterState::default();
+        for i in 0..5 {
+            let id = state.polls.borrow_mut().insert(PollCreateData {
+                description: format!("poll_{i}"),
+                poll_type: PollType::ProjectHash {
+                    project: "key".to_string(),
+                    hash: "hash".to_string(),
+                },
+                start_timestamp_secs: 0,
+                end_timestamp_secs: 10,
+            });
+            state
+                .polls
+                .borrow_mut()
+                .finalize_poll(
+                    id,
+                    11,
+                    &mut state.permissions.borrow_mut(),
+                    &mut state.settings.borrow_mut(),
+                    &mut state.projects.borrow_mut(),
+                )
+                .unwrap();
+        }
+
+        let response = http_request(&state, &get("/polls/closed?start=1&limit=2"));
+        assert_eq!(response.status_code, 200);
+        let polls: BTreeMap<u64, ClosedPoll> = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(polls.keys().copied().collect::<Vec<_>>(), vec![1, 2]);
+
+        let response = http_request(&state, &get("/polls/closed"));
+        let polls: BTreeMap<u64, ClosedPoll> = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(polls.len(), 5);
+
+        let response = http_request(&state, &get("/polls/closed?limit=-1"));
+        assert_eq!(response.status_code, 400);
+    }
+
+    #[test]
+    fn test_json_endpoints() {
+        let state = state_with_data();
+
+        let response = http_request(&state, &get("/projects"));
+        assert_eq!(response.status_code, 200);
+        let projects: Vec<ProjectData> = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(projects, state.projects.borrow().all());
+
+        let response = http_request(&state, &get("/polls/pending"));
+        assert_eq!(response.status_code, 200);
+        let polls: BTreeMap<u64, PendingPoll> = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(polls, state.polls.borrow().all_pending());
+
+        let response = http_request(&state, &get("/polls/closed"));
+        assert_eq!(response.status_code, 200);
+        assert_eq!(response.body, b"{}");
+
+        let response = http_request(&state, &get("/build"));
+        assert_eq!(response.status_code, 200);
+        let build_data: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(build_data["pkg_name"], "upgrader_canister");
+    }
+
+    #[test]
+    fn test_metrics_endpoint() {
+        let state = state_with_data();
+
+        let response = http_request(&state, &get("/metrics"));
+        assert_eq!(response.status_code, 200);
+
+        let body = String::from_utf8(response.body).unwrap();
+        assert!(body.contains("upgrader_polls_pending 1"));
+        assert!(body.contains("upgrader_projects 1"));
+    }
+
+    #[test]
+    fn test_html_overview() {
+        let state = state_with_data();
+
+        let response = http_request(&state, &get("/"));
+        assert_eq!(response.status_code, 200);
+        assert!(response.headers.contains(&(
+            "Content-Type".to_string(),
+            "text/html; charset=utf-8".to_string()
+        )));
+
+        let body = String::from_utf8(response.body).unwrap();
+        assert!(body.contains("Pending polls (1)"));
+        assert!(body.contains("poll_0"));
+        assert!(body.contains("&lt;b&gt;Project&lt;/b&gt;"));
+        assert!(!body.contains("<b>Project</b>"));
+    }
+
+    #[test]
+    fn test_not_found_and_method_not_allowed() {
+        let state = UpgraderCanisterState::default();
+
+        assert_eq!(http_request(&state, &get("/unknown")).status_code, 404);
+
+        let mut request = get("/projects");
+        request.method = "POST".to_string();
+        assert_eq!(http_request(&state, &request).status_code, 405);
+    }
+}
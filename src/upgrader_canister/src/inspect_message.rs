@@ -27,9 +27,12 @@ fn inspect_message_impl(state: &UpgraderCanisterState) {
 
     let check_result = match method.as_str() {
         method if method.starts_with("admin_") => permissions.check_admin(&ic::caller()),
-        "project_create" => crate::canister::project_create_inspect(&permissions, &ic::caller()),
-        "poll_create" => crate::canister::poll_create_inspect(&permissions, &ic::caller()),
-        "poll_vote" => crate::canister::poll_vote_inspect(&permissions, &ic::caller()),
+        "project_create" => crate::endpoints::project_create_inspect(&permissions, &ic::caller()),
+        "poll_create" => crate::endpoints::poll_create_inspect(&permissions, &ic::caller()),
+        "poll_vote" => crate::endpoints::poll_vote_inspect(&permissions, &ic::caller()),
+        "project_deployment_status" => {
+            crate::endpoints::project_deployment_status_inspect(&permissions, &ic::caller())
+        }
         _ => Ok(()),
     };
 
@@ -1,5 +1,8 @@
 pub mod build_data;
 pub mod canister;
 pub mod constant;
+pub mod endpoints;
+pub mod http;
 pub mod inspect_message;
+pub mod metrics;
 pub mod state;
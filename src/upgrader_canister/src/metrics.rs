@@ -0,0 +1,277 @@
+use std::fmt::Write;
+
+use upgrader_canister_did::{Permission, PollResult};
+
+use crate::state::UpgraderCanisterState;
+
+/// Renders the canister metrics in the Prometheus text format
+pub fn render_metrics(state: &UpgraderCanisterState, cycles_balance: u128) -> String {
+    let mut out = String::new();
+
+    let polls = state.polls.borrow();
+    write_gauge(
+        &mut out,
+        "upgrader_polls_pending",
+        "Number of pending polls",
+        &[(String::new(), polls.pending_count() as u128)],
+    );
+
+    let mut accepted = 0;
+    let mut rejected = 0;
+    let mut failed = 0;
+    for poll in polls.all_closed().values() {
+        match poll.result {
+            PollResult::Accepted => accepted += 1,
+            PollResult::Rejected => rejected += 1,
+            PollResult::Failed(_) => failed += 1,
+        }
+    }
+    write_gauge(
+        &mut out,
+        "upgrader_polls_closed",
+        "Number of closed polls by result",
+        &[
+            ("result=\"accepted\"".to_string(), accepted),
+            ("result=\"rejected\"".to_string(), rejected),
+            ("result=\"failed\"".to_string(), failed),
+        ],
+    );
+    write_gauge(
+        &mut out,
+        "upgrader_polls_archived",
+        "Number of closed polls moved to the archive canister",
+        &[(String::new(), polls.archived_count() as u128)],
+    );
+    drop(polls);
+
+    write_gauge(
+        &mut out,
+        "upgrader_projects",
+        "Number of projects",
+        &[(String::new(), state.projects.borrow().all().len() as u128)],
+    );
+
+    let alerts_per_project = state
+        .alerts
+        .borrow()
+        .count_by_project()
+        .into_iter()
+        .map(|(project, count)| {
+            (
+                format!("project=\"{}\"", escape_label_value(&project)),
+                count as u128,
+            )
+        })
+        .collect::<Vec<_>>();
+    write_gauge(
+        &mut out,
+        "upgrader_unapproved_upgrade_alerts",
+        "Number of alerts of unapproved upgrades per project",
+        &alerts_per_project,
+    );
+
+    let permissions = state.permissions.borrow();
+    let principals_per_permission = [
+        ("admin", Permission::Admin),
+        ("create_project", Permission::CreateProject),
+        ("create_poll", Permission::CreatePoll),
+        ("vote_poll", Permission::VotePoll),
+    ]
+    .into_iter()
+    .map(|(label, permission)| {
+        (
+            format!("permission=\"{label}\""),
+            permissions.principals_count(&permission) as u128,
+        )
+    })
+    .collect::<Vec<_>>();
+    write_gauge(
+        &mut out,
+        "upgrader_permission_principals",
+        "Number of principals per permission",
+        &principals_per_permission,
+    );
+    drop(permissions);
+
+    let memory_pages = state
+        .memory_pages()
+        .into_iter()
+        .map(|(name, id, pages)| {
+            (
+                format!("memory=\"{name}\",memory_id=\"{id}\""),
+                pages as u128,
+            )
+        })
+        .collect::<Vec<_>>();
+    write_gauge(
+        &mut out,
+        "upgrader_stable_memory_pages",
+        "Size of the stable memory in 64KiB pages by memory id",
+        &memory_pages,
+    );
+
+    write_gauge(
+        &mut out,
+        "upgrader_cycles_balance",
+        "Cycles balance of the canister",
+        &[(String::new(), cycles_balance)],
+    );
+    write_gauge(
+        &mut out,
+        "upgrader_last_timer_run_timestamp_seconds",
+        "Timestamp of the last run of the poll timer",
+        &[(String::new(), state.last_timer_run_secs.get() as u128)],
+    );
+
+    out
+}
+
+/// Writes a gauge metric with the given labelled values
+fn write_gauge(out: &mut String, name: &str, help: &str, values: &[(String, u128)]) {
+    // Writing to a String never fails
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} gauge");
+    for (labels, value) in values {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{name} {value}");
+        } else {
+            let _ = writeln!(out, "{name}{{{labels}}} {value}");
+        }
+    }
+}
+
+/// Escapes a label value as required by the Prometheus text format:
+/// the backslash, the double quote and the line feed are escaped with a backslash
+fn escape_label_value(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::Principal;
+    use upgrader_canister_did::{PollCreateData, PollType, ProjectCanister, ProjectData, WasmHash};
+
+    use super::*;
+    use crate::state::alerts::ModuleDeployment;
+
+    #[test]
+    fn test_render_metrics() {
+        // Arrange
+        let state = UpgraderCanisterState::default();
+        state
+            .projects
+            .borrow_mut()
+            .insert(ProjectData {
+                key: "key".to_string(),
+                name: "Project".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+        state
+            .permissions
+            .borrow_mut()
+            .add_permissions(Principal::from_slice(&[1; 29]), vec![Permission::Admin])
+            .unwrap();
+        for end_timestamp_secs in [10, 20] {
+            state.polls.borrow_mut().insert(PollCreateData {
+                description: "poll".to_string(),
+                poll_type: PollType::ProjectHash {
+                    project: "key".to_string(),
+                    hash: "hash".to_string(),
+                },
+                start_timestamp_secs: 0,
+                end_timestamp_secs,
+            });
+        }
+        state.polls.borrow_mut().finalize_polls(
+            15,
+            &mut state.permissions.borrow_mut(),
+            &mut state.settings.borrow_mut(),
+            &mut state.projects.borrow_mut(),
+            || true,
+        );
+        state.last_timer_run_secs.set(15);
+        state.alerts.borrow_mut().check_deployments(
+            &state.projects.borrow(),
+            &"key".to_string(),
+            &ProjectCanister {
+                network: "ic".to_string(),
+                canister_id: Principal::from_slice(&[2; 10]),
+            },
+            &[ModuleDeployment {
+                canister_version: 1,
+                timestamp_nanos: 0,
+                module_hash: WasmHash([1; 32]),
+            }],
+            15,
+        );
+
+        // Act
+        let metrics = render_metrics(&state, 1_000);
+
+        // Assert
+        assert!(metrics.contains("# TYPE upgrader_polls_pending gauge\n"));
+        assert!(metrics.contains("\nupgrader_polls_pending 1\n"));
+        assert!(metrics.contains("\nupgrader_polls_closed{result=\"accepted\"} 0\n"));
+        assert!(metrics.contains("\nupgrader_polls_closed{result=\"rejected\"} 1\n"));
+        assert!(metrics.contains("\nupgrader_polls_closed{result=\"failed\"} 0\n"));
+        assert!(metrics.contains("\nupgrader_polls_archived 0\n"));
+        assert!(metrics.contains("\nupgrader_projects 1\n"));
+        assert!(metrics.contains("\nupgrader_permission_principals{permission=\"admin\"} 1\n"));
+        assert!(metrics.contains("\nupgrader_permission_principals{permission=\"vote_poll\"} 0\n"));
+        assert!(metrics
+            .contains("upgrader_stable_memory_pages{memory=\"projects_map\",memory_id=\"2\"}"));
+        assert!(metrics.contains("\nupgrader_unapproved_upgrade_alerts{project=\"key\"} 1\n"));
+        assert!(metrics.contains("\nupgrader_cycles_balance 1000\n"));
+        assert!(metrics.contains("\nupgrader_last_timer_run_timestamp_seconds 15\n"));
+    }
+
+    #[test]
+    fn test_render_metrics_escapes_label_values() {
+        // Arrange
+        let state = UpgraderCanisterState::default();
+        let key = "a\\b\"c\nd".to_string();
+        state
+            .projects
+            .borrow_mut()
+            .insert(ProjectData {
+                key: key.clone(),
+                name: "Project".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+        state.alerts.borrow_mut().check_deployments(
+            &state.projects.borrow(),
+            &key,
+            &ProjectCanister {
+                network: "ic".to_string(),
+                canister_id: Principal::from_slice(&[2; 10]),
+            },
+            &[ModuleDeployment {
+                canister_version: 1,
+                timestamp_nanos: 0,
+                module_hash: WasmHash([1; 32]),
+            }],
+            15,
+        );
+
+        // Act
+        let metrics = render_metrics(&state, 1_000);
+
+        // Assert
+        assert!(metrics
+            .contains("\nupgrader_unapproved_upgrade_alerts{project=\"a\\\\b\\\"c\\nd\"} 1\n"));
+    }
+}
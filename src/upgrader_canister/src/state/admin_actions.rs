@@ -0,0 +1,244 @@
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{
+    BTreeMapStructure, CellStructure, MemoryManager, StableBTreeMap, StableCell,
+};
+use upgrader_canister_did::error::{Result, UpgraderError};
+use upgrader_canister_did::{AdminAction, PendingAdminAction};
+
+use crate::constant::{ADMIN_ACTIONS_ID_SEQUENCE_MEMORY_ID, ADMIN_ACTIONS_MAP_MEMORY_ID};
+
+/// Manages the admin actions waiting for the approval of multiple admins
+pub struct AdminActions<M: Memory> {
+    /// Contains the admin actions that are not yet executed
+    pending_actions: StableBTreeMap<u64, PendingAdminAction, M>,
+    /// The next admin action id
+    actions_id_sequence: StableCell<u64, M>,
+}
+
+impl<M: Memory> AdminActions<M> {
+    pub fn new(memory_manager: &dyn MemoryManager<M, u8>) -> Self {
+        Self {
+            pending_actions: StableBTreeMap::new(memory_manager.get(ADMIN_ACTIONS_MAP_MEMORY_ID)),
+            actions_id_sequence: StableCell::new(
+                memory_manager.get(ADMIN_ACTIONS_ID_SEQUENCE_MEMORY_ID),
+                0,
+            )
+            .expect("stable memory ADMIN_ACTIONS_ID_SEQUENCE_MEMORY_ID initialization failed"),
+        }
+    }
+
+    /// Returns the pending admin action with the given id
+    pub fn get(&self, id: &u64) -> Option<PendingAdminAction> {
+        self.pending_actions.get(id)
+    }
+
+    /// Returns all the pending admin actions that are not expired
+    pub fn all_pending(&self, timestamp_secs: u64) -> BTreeMap<u64, PendingAdminAction> {
+        self.pending_actions
+            .iter()
+            .filter(|(_, action)| timestamp_secs <= action.expiry_timestamp_secs)
+            .collect()
+    }
+
+    /// Registers a new admin action approved by the proposer and returns the generated id.
+    /// Expired actions are removed.
+    pub fn propose(
+        &mut self,
+        action: AdminAction,
+        proposer: Principal,
+        timestamp_secs: u64,
+        expiry_secs: u64,
+    ) -> u64 {
+        self.remove_expired(timestamp_secs);
+
+        let id = self.next_id();
+        self.pending_actions.insert(
+            id,
+            PendingAdminAction {
+                action,
+                approvals: vec![proposer],
+                created_timestamp_secs: timestamp_secs,
+                expiry_timestamp_secs: timestamp_secs.saturating_add(expiry_secs),
+            },
+        );
+        id
+    }
+
+    /// Adds the approval of an admin to a pending action and returns the updated action.
+    /// If the admin has already approved the action, the approval is not counted twice.
+    pub fn approve(
+        &mut self,
+        id: u64,
+        approver: Principal,
+        timestamp_secs: u64,
+    ) -> Result<PendingAdminAction> {
+        let mut action = self
+            .pending_actions
+            .get(&id)
+            .ok_or(UpgraderError::AdminActionNotFound(id))?;
+
+        if timestamp_secs > action.expiry_timestamp_secs {
+            self.pending_actions.remove(&id);
+            return Err(UpgraderError::AdminActionExpired {
+                expired_at: action.expiry_timestamp_secs,
+            });
+        }
+
+        if !action.approvals.contains(&approver) {
+            action.approvals.push(approver);
+            self.pending_actions.insert(id, action.clone());
+        }
+
+        Ok(action)
+    }
+
+    /// Removes a pending admin action
+    pub fn remove(&mut self, id: &u64) -> Option<PendingAdminAction> {
+        self.pending_actions.remove(id)
+    }
+
+    /// Removes all the expired admin actions
+    pub fn remove_expired(&mut self, timestamp_secs: u64) {
+        let expired = self
+            .pending_actions
+            .iter()
+            .filter(|(_, action)| timestamp_secs > action.expiry_timestamp_secs)
+            .map(|(id, _)| id)
+            .collect::<Vec<_>>();
+
+        for id in expired {
+            self.pending_actions.remove(&id);
+        }
+    }
+
+    /// Returns the next admin action id
+    fn next_id(&mut self) -> u64 {
+        let id = *self.actions_id_sequence.get();
+        self.actions_id_sequence
+            .set(id + 1)
+            .expect("Unable to access the stable storage to set the next admin action id");
+        id
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::Principal;
+    use upgrader_canister_did::{AdminAction, Permission, UpgraderError};
+
+    use super::AdminActions;
+
+    fn add_permission_action() -> AdminAction {
+        AdminAction::PermissionsAdd {
+            principal: Principal::from_slice(&[9; 29]),
+            permissions: vec![Permission::CreatePoll],
+        }
+    }
+
+    /// Should register a new action approved by the proposer
+    #[test]
+    fn test_propose_action() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut actions = AdminActions::new(&memory_manager);
+        let principal_1 = Principal::from_slice(&[1; 29]);
+
+        // Act
+        let id_0 = actions.propose(add_permission_action(), principal_1, 100, 50);
+        let id_1 = actions.propose(
+            AdminAction::DisableInspectMessage(true),
+            principal_1,
+            100,
+            50,
+        );
+
+        // Assert
+        assert_eq!(id_0, 0);
+        assert_eq!(id_1, 1);
+
+        let action = actions.get(&id_0).unwrap();
+        assert_eq!(action.action, add_permission_action());
+        assert_eq!(action.approvals, vec![principal_1]);
+        assert_eq!(action.created_timestamp_secs, 100);
+        assert_eq!(action.expiry_timestamp_secs, 150);
+    }
+
+    /// Should count the approvals of distinct admins only once
+    #[test]
+    fn test_approve_action() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut actions = AdminActions::new(&memory_manager);
+        let principal_1 = Principal::from_slice(&[1; 29]);
+        let principal_2 = Principal::from_slice(&[2; 29]);
+        let id = actions.propose(add_permission_action(), principal_1, 0, 50);
+
+        // Act
+        actions.approve(id, principal_1, 10).unwrap();
+        actions.approve(id, principal_2, 10).unwrap();
+        let action = actions.approve(id, principal_2, 20).unwrap();
+
+        // Assert
+        assert_eq!(action.approvals, vec![principal_1, principal_2]);
+        assert_eq!(actions.get(&id).unwrap().approvals, action.approvals);
+    }
+
+    /// Should return an error if approving an action that does not exist
+    #[test]
+    fn test_approve_action_not_found() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut actions = AdminActions::new(&memory_manager);
+
+        // Act
+        let result = actions.approve(0, Principal::from_slice(&[1; 29]), 0);
+
+        // Assert
+        assert_eq!(result, Err(UpgraderError::AdminActionNotFound(0)));
+    }
+
+    /// Should reject and remove expired actions
+    #[test]
+    fn test_approve_expired_action() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut actions = AdminActions::new(&memory_manager);
+        let principal_1 = Principal::from_slice(&[1; 29]);
+        let principal_2 = Principal::from_slice(&[2; 29]);
+        let id = actions.propose(add_permission_action(), principal_1, 0, 50);
+
+        // Act & Assert
+        assert!(actions.approve(id, principal_2, 50).is_ok());
+        assert_eq!(
+            actions.approve(id, principal_2, 51),
+            Err(UpgraderError::AdminActionExpired { expired_at: 50 })
+        );
+        assert!(actions.get(&id).is_none());
+    }
+
+    /// Should list only the actions that are not expired
+    #[test]
+    fn test_all_pending_and_remove_expired() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut actions = AdminActions::new(&memory_manager);
+        let principal_1 = Principal::from_slice(&[1; 29]);
+        let id_0 = actions.propose(add_permission_action(), principal_1, 0, 10);
+        let id_1 = actions.propose(add_permission_action(), principal_1, 0, 100);
+
+        // Act & Assert
+        assert_eq!(actions.all_pending(10).len(), 2);
+
+        let pending = actions.all_pending(11);
+        assert_eq!(pending.len(), 1);
+        assert!(pending.contains_key(&id_1));
+
+        actions.remove_expired(11);
+        assert!(actions.get(&id_0).is_none());
+        assert!(actions.get(&id_1).is_some());
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{
+    BTreeMapStructure, LogStructure, MemoryManager, StableBTreeMap, StableLog,
+};
+use upgrader_canister_did::{ProjectCanister, UnapprovedUpgradeAlert, WasmHash};
+
+use super::projects::Projects;
+use crate::constant::{
+    ALERTS_CHECKED_VERSIONS_MAP_MEMORY_ID, ALERTS_LOG_DATA_MEMORY_ID, ALERTS_LOG_INDEX_MEMORY_ID,
+    ALERTS_MAX_PAGE_SIZE,
+};
+
+/// The installation of a module found in the history of a canister
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDeployment {
+    /// The version of the canister after the installation
+    pub canister_version: u64,
+    /// The timestamp in nanoseconds of the installation
+    pub timestamp_nanos: u64,
+    /// The hash of the installed module
+    pub module_hash: WasmHash,
+}
+
+/// Records the alerts raised when a canister of a project runs a module that was never approved
+pub struct Alerts<M: Memory> {
+    alerts: StableLog<UnapprovedUpgradeAlert, M>,
+    /// The last version checked for each canister
+    checked_versions: StableBTreeMap<Principal, u64, M>,
+}
+
+impl<M: Memory> Alerts<M> {
+    pub fn new(memory_manager: &dyn MemoryManager<M, u8>) -> Self {
+        Self {
+            alerts: StableLog::new(
+                memory_manager.get(ALERTS_LOG_INDEX_MEMORY_ID),
+                memory_manager.get(ALERTS_LOG_DATA_MEMORY_ID),
+            )
+            .expect("stable memory ALERTS_LOG_INDEX_MEMORY_ID initialization failed"),
+            checked_versions: StableBTreeMap::new(
+                memory_manager.get(ALERTS_CHECKED_VERSIONS_MAP_MEMORY_ID),
+            ),
+        }
+    }
+
+    /// Checks the modules installed in a canister of the project and records an alert
+    /// for each module hash that was never approved by a `ProjectHash` poll.
+    /// The deployments up to the last checked version of the canister are skipped.
+    /// Returns the number of recorded alerts.
+    pub fn check_deployments(
+        &mut self,
+        projects: &Projects<M>,
+        project: &String,
+        canister: &ProjectCanister,
+        deployments: &[ModuleDeployment],
+        timestamp_secs: u64,
+    ) -> usize {
+        let last_checked_version = self.checked_versions.get(&canister.canister_id);
+        let mut recorded = 0;
+
+        for deployment in deployments {
+            if last_checked_version.is_some_and(|version| deployment.canister_version <= version) {
+                continue;
+            }
+
+            let module_hash = deployment.module_hash.to_string();
+            if projects.hash_status(project, &module_hash).is_none() {
+                self.append(UnapprovedUpgradeAlert {
+                    project: project.clone(),
+                    canister: canister.clone(),
+                    module_hash,
+                    canister_version: deployment.canister_version,
+                    installed_timestamp_nanos: deployment.timestamp_nanos,
+                    detected_timestamp_secs: timestamp_secs,
+                });
+                recorded += 1;
+            }
+        }
+
+        let checked_version = deployments
+            .iter()
+            .map(|deployment| deployment.canister_version)
+            .chain(last_checked_version)
+            .max();
+        if let Some(version) = checked_version {
+            self.checked_versions.insert(canister.canister_id, version);
+        }
+
+        recorded
+    }
+
+    /// Appends an alert to the log and returns its index
+    fn append(&mut self, alert: UnapprovedUpgradeAlert) -> u64 {
+        self.alerts
+            .append(alert)
+            .expect("Unable to access the stable storage to append an alert")
+    }
+
+    /// Returns the alert at the given index
+    pub fn get(&self, index: u64) -> Option<UnapprovedUpgradeAlert> {
+        self.alerts.get(index)
+    }
+
+    /// Returns the number of recorded alerts
+    pub fn len(&self) -> u64 {
+        self.alerts.len()
+    }
+
+    /// Returns true if no alert was recorded
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns up to `limit` alerts starting from the `start` index.
+    /// The number of returned alerts is capped to `ALERTS_MAX_PAGE_SIZE`.
+    pub fn page(&self, start: u64, limit: u64) -> BTreeMap<u64, UnapprovedUpgradeAlert> {
+        let end = start
+            .saturating_add(limit.min(ALERTS_MAX_PAGE_SIZE))
+            .min(self.len());
+
+        (start..end)
+            .filter_map(|index| self.get(index).map(|alert| (index, alert)))
+            .collect()
+    }
+
+    /// Returns the number of alerts recorded for each project
+    pub fn count_by_project(&self) -> BTreeMap<String, u64> {
+        let mut counts = BTreeMap::new();
+        for index in 0..self.len() {
+            if let Some(alert) = self.get(index) {
+                *counts.entry(alert.project).or_default() += 1;
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::Principal;
+    use upgrader_canister_did::{ProjectCanister, ProjectData, WasmHash};
+
+    use super::{Alerts, ModuleDeployment};
+    use crate::state::projects::Projects;
+
+    fn deployment(canister_version: u64, hash_byte: u8) -> ModuleDeployment {
+        ModuleDeployment {
+            canister_version,
+            timestamp_nanos: canister_version * 1_000,
+            module_hash: WasmHash([hash_byte; 32]),
+        }
+    }
+
+    /// Should record an alert for each module hash that was never approved
+    #[test]
+    fn test_check_deployments() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut alerts = Alerts::new(&memory_manager);
+        let mut projects = Projects::new(&memory_manager);
+        let project = "project".to_string();
+        projects
+            .insert(ProjectData {
+                key: project.clone(),
+                name: "Project".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+        projects
+            .approve_hash(&project, &WasmHash([1; 32]).to_string(), 0)
+            .unwrap();
+        let canister = ProjectCanister {
+            network: "ic".to_string(),
+            canister_id: Principal::from_slice(&[1; 10]),
+        };
+
+        // Act
+        let recorded = alerts.check_deployments(
+            &projects,
+            &project,
+            &canister,
+            &[deployment(1, 1), deployment(2, 2)],
+            10,
+        );
+
+        // Assert
+        assert_eq!(recorded, 1);
+        assert_eq!(alerts.len(), 1);
+        let alert = alerts.get(0).unwrap();
+        assert_eq!(alert.project, project);
+        assert_eq!(alert.canister, canister);
+        assert_eq!(alert.module_hash, WasmHash([2; 32]).to_string());
+        assert_eq!(alert.canister_version, 2);
+        assert_eq!(alert.installed_timestamp_nanos, 2_000);
+        assert_eq!(alert.detected_timestamp_secs, 10);
+    }
+
+    /// Should not record again the deployments already checked
+    #[test]
+    fn test_check_deployments_skips_checked_versions() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut alerts = Alerts::new(&memory_manager);
+        let projects = Projects::new(&memory_manager);
+        let project = "project".to_string();
+        let canister = ProjectCanister {
+            network: "ic".to_string(),
+            canister_id: Principal::from_slice(&[1; 10]),
+        };
+        alerts.check_deployments(&projects, &project, &canister, &[deployment(1, 1)], 10);
+
+        // Act
+        let recorded = alerts.check_deployments(
+            &projects,
+            &project,
+            &canister,
+            &[deployment(1, 1), deployment(3, 3)],
+            20,
+        );
+
+        // Assert
+        assert_eq!(recorded, 1);
+        assert_eq!(alerts.len(), 2);
+        assert_eq!(alerts.get(1).unwrap().canister_version, 3);
+        assert_eq!(alerts.count_by_project()[&project], 2);
+        assert_eq!(alerts.page(1, 10).len(), 1);
+    }
+}
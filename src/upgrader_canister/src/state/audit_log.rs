@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+
+use ic_stable_structures::stable_structures::Memory;
+use ic_stable_structures::{LogStructure, MemoryManager, StableLog};
+use upgrader_canister_did::AuditLogEntry;
+
+use crate::constant::{
+    AUDIT_LOG_ARGS_MAX_LENGTH, AUDIT_LOG_DATA_MEMORY_ID, AUDIT_LOG_INDEX_MEMORY_ID,
+    AUDIT_LOG_MAX_PAGE_SIZE,
+};
+
+/// Append-only log of the state-changing calls
+pub struct AuditLog<M: Memory> {
+    entries: StableLog<AuditLogEntry, M>,
+}
+
+impl<M: Memory> AuditLog<M> {
+    pub fn new(memory_manager: &dyn MemoryManager<M, u8>) -> Self {
+        Self {
+            entries: StableLog::new(
+                memory_manager.get(AUDIT_LOG_INDEX_MEMORY_ID),
+                memory_manager.get(AUDIT_LOG_DATA_MEMORY_ID),
+            )
+            .expect("stable memory AUDIT_LOG_INDEX_MEMORY_ID initialization failed"),
+        }
+    }
+
+    /// Appends an entry to the log and returns its index.
+    /// The arguments summary is truncated to `AUDIT_LOG_ARGS_MAX_LENGTH` characters.
+    pub fn append(&mut self, mut entry: AuditLogEntry) -> u64 {
+        if entry.args.chars().count() > AUDIT_LOG_ARGS_MAX_LENGTH {
+            entry.args = entry.args.chars().take(AUDIT_LOG_ARGS_MAX_LENGTH).collect();
+            entry.args.push_str("...");
+        }
+
+        self.entries
+            .append(entry)
+            .expect("Unable to access the stable storage to append an audit log entry")
+    }
+
+    /// Returns the entry at the given index
+    pub fn get(&self, index: u64) -> Option<AuditLogEntry> {
+        self.entries.get(index)
+    }
+
+    /// Returns the number of entries in the log
+    pub fn len(&self) -> u64 {
+        self.entries.len()
+    }
+
+    /// Returns true if the log has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns up to `limit` entries starting from the `start` index.
+    /// The number of returned entries is capped to `AUDIT_LOG_MAX_PAGE_SIZE`.
+    pub fn page(&self, start: u64, limit: u64) -> BTreeMap<u64, AuditLogEntry> {
+        let end = start
+            .saturating_add(limit.min(AUDIT_LOG_MAX_PAGE_SIZE))
+            .min(self.len());
+
+        (start..end)
+            .filter_map(|index| self.get(index).map(|entry| (index, entry)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::Principal;
+    use upgrader_canister_did::{AuditLogEntry, AuditLogOutcome};
+
+    use super::AuditLog;
+    use crate::constant::{AUDIT_LOG_ARGS_MAX_LENGTH, AUDIT_LOG_MAX_PAGE_SIZE};
+
+    fn entry(method: &str, args: &str) -> AuditLogEntry {
+        AuditLogEntry {
+            caller: Principal::from_slice(&[1; 29]),
+            method: method.to_string(),
+            args: args.to_string(),
+            timestamp_secs: 0,
+            outcome: AuditLogOutcome::Success,
+        }
+    }
+
+    /// Should append the entries in order
+    #[test]
+    fn test_append_entries() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut log = AuditLog::new(&memory_manager);
+
+        // Act
+        let index_0 = log.append(entry("project_create", "project_0"));
+        let index_1 = log.append(entry("poll_create", "poll_0"));
+
+        // Assert
+        assert_eq!(index_0, 0);
+        assert_eq!(index_1, 1);
+        assert_eq!(log.len(), 2);
+        assert_eq!(log.get(0).unwrap().method, "project_create");
+        assert_eq!(log.get(1).unwrap().method, "poll_create");
+        assert!(log.get(2).is_none());
+    }
+
+    /// Should truncate long arguments summaries
+    #[test]
+    fn test_append_truncates_args() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut log = AuditLog::new(&memory_manager);
+        let args = "a".repeat(AUDIT_LOG_ARGS_MAX_LENGTH * 2);
+
+        // Act
+        let index = log.append(entry("poll_create", &args));
+
+        // Assert
+        let stored_args = log.get(index).unwrap().args;
+        assert_eq!(stored_args.len(), AUDIT_LOG_ARGS_MAX_LENGTH + 3);
+        assert!(stored_args.ends_with("..."));
+    }
+
+    /// Should return the requested page of entries
+    #[test]
+    fn test_page() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut log = AuditLog::new(&memory_manager);
+        for i in 0..(AUDIT_LOG_MAX_PAGE_SIZE + 10) {
+            log.append(entry("poll_vote", &i.to_string()));
+        }
+
+        // Act & Assert
+        let page = log.page(5, 3);
+        assert_eq!(page.keys().copied().collect::<Vec<_>>(), vec![5, 6, 7]);
+        assert_eq!(page[&5].args, "5");
+
+        assert_eq!(log.page(0, u64::MAX).len() as u64, AUDIT_LOG_MAX_PAGE_SIZE);
+        assert_eq!(log.page(AUDIT_LOG_MAX_PAGE_SIZE + 5, 10).len(), 5);
+        assert!(log.page(u64::MAX, 10).is_empty());
+    }
+}
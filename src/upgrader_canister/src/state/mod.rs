@@ -1,23 +1,51 @@
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 
-use ic_stable_structures::stable_structures::DefaultMemoryImpl;
-use ic_stable_structures::{default_ic_memory_manager, VirtualMemory};
+use admin_actions::AdminActions;
+use alerts::Alerts;
+use audit_log::AuditLog;
+use ic_stable_structures::stable_structures::{DefaultMemoryImpl, Memory};
+use ic_stable_structures::{default_ic_memory_manager, MemoryManager, VirtualMemory};
 use permission::Permissions;
 use polls::Polls;
 use settings::Settings;
 
+use crate::constant::MEMORY_IDS;
+
+pub mod admin_actions;
+pub mod alerts;
+pub mod audit_log;
 pub mod permission;
 pub mod polls;
 pub mod projects;
 pub mod settings;
 
+#[cfg(test)]
+mod state_machine_test;
+
 /// State of the upgrader canister
 pub struct UpgraderCanisterState {
+    pub admin_actions: Rc<RefCell<AdminActions<VirtualMemory<DefaultMemoryImpl>>>>,
+    pub alerts: Rc<RefCell<Alerts<VirtualMemory<DefaultMemoryImpl>>>>,
+    pub audit_log: Rc<RefCell<AuditLog<VirtualMemory<DefaultMemoryImpl>>>>,
     pub permissions: Rc<RefCell<Permissions<VirtualMemory<DefaultMemoryImpl>>>>,
     pub polls: Rc<RefCell<Polls<VirtualMemory<DefaultMemoryImpl>>>>,
     pub projects: Rc<RefCell<projects::Projects<VirtualMemory<DefaultMemoryImpl>>>>,
     pub settings: Rc<RefCell<Settings<VirtualMemory<DefaultMemoryImpl>>>>,
+    /// The timestamp of the last run of the poll timer. It is not persisted across upgrades.
+    pub last_timer_run_secs: Cell<u64>,
+    /// The stable memories used by the canister, by name and memory id
+    memories: Vec<(&'static str, u8, VirtualMemory<DefaultMemoryImpl>)>,
+}
+
+impl UpgraderCanisterState {
+    /// Returns the size in pages of each stable memory used by the canister
+    pub fn memory_pages(&self) -> Vec<(&'static str, u8, u64)> {
+        self.memories
+            .iter()
+            .map(|(name, id, memory)| (*name, *id, memory.size()))
+            .collect()
+    }
 }
 
 impl Default for UpgraderCanisterState {
@@ -25,10 +53,18 @@ impl Default for UpgraderCanisterState {
         let memory_manager = default_ic_memory_manager();
 
         Self {
+            admin_actions: Rc::new(RefCell::new(AdminActions::new(&memory_manager))),
+            alerts: Rc::new(RefCell::new(Alerts::new(&memory_manager))),
+            audit_log: Rc::new(RefCell::new(AuditLog::new(&memory_manager))),
             permissions: Rc::new(RefCell::new(Permissions::new(&memory_manager))),
             polls: Rc::new(RefCell::new(Polls::new(&memory_manager))),
             projects: Rc::new(RefCell::new(projects::Projects::new(&memory_manager))),
             settings: Rc::new(RefCell::new(Settings::new(&memory_manager))),
+            last_timer_run_secs: Cell::new(0),
+            memories: MEMORY_IDS
+                .iter()
+                .map(|(name, id)| (*name, *id, memory_manager.get(*id)))
+                .collect(),
         }
     }
 }
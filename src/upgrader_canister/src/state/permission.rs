@@ -1,3 +1,5 @@
+use std::collections::BTreeSet;
+
 use candid::Principal;
 use ic_stable_structures::stable_structures::Memory;
 use ic_stable_structures::{BTreeMapStructure, MemoryManager, StableBTreeMap};
@@ -24,16 +26,22 @@ impl<M: Memory> Permissions<M> {
         self.check_has_all_permissions(principal, &[Permission::Admin])
     }
 
-    /// Returns NotAuthorized error if the user does not have all permissions
+    /// Returns MissingPermissions error with the permissions the user does not have
     pub fn check_has_all_permissions(
         &self,
         principal: &Principal,
         permissions: &[Permission],
     ) -> Result<()> {
-        if self.has_all_permissions(principal, permissions) {
+        let user_permissions = self.get_permissions(principal);
+        let missing: Vec<Permission> = permissions
+            .iter()
+            .filter(|item| !user_permissions.permissions.contains(item))
+            .cloned()
+            .collect();
+        if missing.is_empty() {
             Ok(())
         } else {
-            Err(UpgraderError::NotAuthorized)
+            Err(UpgraderError::MissingPermissions(missing))
         }
     }
 
@@ -48,7 +56,8 @@ impl<M: Memory> Permissions<M> {
         }
     }
 
-    /// Returns NotAuthorized error if the user does not have at least one of the permissions
+    /// Returns MissingPermissions error with all the permissions
+    /// if the user does not have at least one of them
     pub fn check_has_any_permission(
         &self,
         principal: &Principal,
@@ -57,7 +66,7 @@ impl<M: Memory> Permissions<M> {
         if self.has_any_permission(principal, permissions) {
             Ok(())
         } else {
-            Err(UpgraderError::NotAuthorized)
+            Err(UpgraderError::MissingPermissions(permissions.to_vec()))
         }
     }
 
@@ -115,17 +124,54 @@ impl<M: Memory> Permissions<M> {
         Ok(existing_permissions)
     }
 
+    /// Replaces the permissions of a user, removing the user if the list is empty
+    pub fn set_permissions(&mut self, principal: Principal, permissions: PermissionList) {
+        if permissions.permissions.is_empty() {
+            self.permission_data.remove(&principal);
+        } else {
+            self.permission_data.insert(principal, permissions);
+        }
+    }
+
     /// Return the user permissions
     pub fn get_permissions(&self, principal: &Principal) -> PermissionList {
         self.permission_data.get(principal).unwrap_or_default()
     }
 
+    /// Returns the number of principals that have the given permission
+    pub fn principals_count(&self, permission: &Permission) -> u64 {
+        self.permission_data
+            .iter()
+            .filter(|(_, permissions_list)| permissions_list.permissions.contains(permission))
+            .count() as u64
+    }
+
+    /// Returns the number of admins left after removing the permissions from the principals
+    pub fn admins_count_after_removal(
+        &self,
+        principals: &[Principal],
+        permissions: &[Permission],
+    ) -> u64 {
+        let admins_count = self.principals_count(&Permission::Admin);
+        if !permissions.contains(&Permission::Admin) {
+            return admins_count;
+        }
+        let removed_admins = principals
+            .iter()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|principal| self.check_admin(principal).is_ok())
+            .count() as u64;
+        admins_count - removed_admins
+    }
+
     /// Clear the Whitelist state
     pub fn clear(&mut self) {
         self.permission_data.clear()
     }
 
-    fn check_anonymous_principal(&self, principal: &Principal) -> Result<()> {
+    /// Returns an error if the principal is the anonymous principal
+    pub fn check_anonymous_principal(&self, principal: &Principal) -> Result<()> {
         if principal == &Principal::anonymous() {
             return Err(UpgraderError::AnonymousPrincipalNotAllowed);
         }
@@ -461,7 +507,9 @@ mod tests {
 
         // Assert
         assert_eq!(
-            Err(UpgraderError::NotAuthorized),
+            Err(UpgraderError::MissingPermissions(vec![
+                Permission::VotePoll
+            ])),
             permissions.check_has_all_permissions(
                 &principal_1,
                 &[Permission::CreatePoll, Permission::VotePoll]
@@ -484,7 +532,9 @@ mod tests {
             .check_has_any_permission(&principal_1, &[Permission::CreatePoll])
             .is_ok());
         assert_eq!(
-            Err(UpgraderError::NotAuthorized),
+            Err(UpgraderError::MissingPermissions(vec![
+                Permission::VotePoll
+            ])),
             permissions.check_has_any_permission(&principal_1, &[Permission::VotePoll])
         );
     }
@@ -497,7 +547,7 @@ mod tests {
 
         let principal_1 = Principal::from_slice(&[1; 29]);
         assert_eq!(
-            Err(UpgraderError::NotAuthorized),
+            Err(UpgraderError::MissingPermissions(vec![Permission::Admin])),
             permissions.check_admin(&principal_1)
         );
 
@@ -505,7 +555,7 @@ mod tests {
             .add_permissions(principal_1, vec![Permission::CreatePoll])
             .unwrap();
         assert_eq!(
-            Err(UpgraderError::NotAuthorized),
+            Err(UpgraderError::MissingPermissions(vec![Permission::Admin])),
             permissions.check_admin(&principal_1)
         );
 
@@ -518,11 +568,71 @@ mod tests {
             .remove_permissions(principal_1, &[Permission::Admin])
             .unwrap();
         assert_eq!(
-            Err(UpgraderError::NotAuthorized),
+            Err(UpgraderError::MissingPermissions(vec![Permission::Admin])),
             permissions.check_admin(&principal_1)
         );
     }
 
+    #[test]
+    fn should_count_principals_per_permission() {
+        // Arrange
+        MockContext::new().inject();
+        let mut permissions = Permissions::new(&default_ic_memory_manager());
+        permissions.clear();
+
+        let principal_1 = Principal::from_slice(&[1; 29]);
+        let principal_2 = Principal::from_slice(&[2; 29]);
+
+        permissions
+            .add_permissions(principal_1, vec![Permission::Admin, Permission::VotePoll])
+            .unwrap();
+        permissions
+            .add_permissions(principal_2, vec![Permission::VotePoll])
+            .unwrap();
+
+        // Assert
+        assert_eq!(permissions.principals_count(&Permission::Admin), 1);
+        assert_eq!(permissions.principals_count(&Permission::VotePoll), 2);
+        assert_eq!(permissions.principals_count(&Permission::CreatePoll), 0);
+    }
+
+    #[test]
+    fn should_count_admins_after_removal() {
+        // Arrange
+        MockContext::new().inject();
+        let mut permissions = Permissions::new(&default_ic_memory_manager());
+        permissions.clear();
+
+        let principal_1 = Principal::from_slice(&[1; 29]);
+        let principal_2 = Principal::from_slice(&[2; 29]);
+        let principal_3 = Principal::from_slice(&[3; 29]);
+
+        permissions
+            .add_permissions(principal_1, vec![Permission::Admin])
+            .unwrap();
+        permissions
+            .add_permissions(principal_2, vec![Permission::Admin])
+            .unwrap();
+
+        // Act & Assert
+        assert_eq!(
+            permissions.admins_count_after_removal(&[principal_1], &[Permission::VotePoll]),
+            2
+        );
+        assert_eq!(
+            permissions.admins_count_after_removal(
+                &[principal_1, principal_1, principal_3],
+                &[Permission::Admin]
+            ),
+            1
+        );
+        assert_eq!(
+            permissions
+                .admins_count_after_removal(&[principal_1, principal_2], &[Permission::Admin]),
+            0
+        );
+    }
+
     #[test]
     fn check_anonymous_principal_is_rejected() {
         // Arrange
@@ -5,12 +5,18 @@ use ic_stable_structures::stable_structures::Memory;
 use ic_stable_structures::{
     BTreeMapStructure, CellStructure, MemoryManager, StableBTreeMap, StableCell,
 };
+use log::warn;
 use upgrader_canister_did::error::{Result, UpgraderError};
-use upgrader_canister_did::{ClosedPoll, PendingPoll, Poll, PollCreateData, PollResult};
+use upgrader_canister_did::{
+    ClosedPoll, PendingPoll, Permission, Poll, PollAction, PollCreateData, PollResult, PollType,
+};
 
 use super::permission::Permissions;
+use super::projects::Projects;
+use super::settings::Settings;
 use crate::constant::{
-    POLLS_CLOSED_MAP_MEMORY_ID, POLLS_ID_SEQUENCE_MEMORY_ID, POLLS_PENDING_MAP_MEMORY_ID,
+    CLOSED_POLLS_MAX_PAGE_SIZE, POLLS_ARCHIVED_INDEX_MEMORY_ID, POLLS_CLOSED_MAP_MEMORY_ID,
+    POLLS_ID_SEQUENCE_MEMORY_ID, POLLS_PENDING_MAP_MEMORY_ID,
 };
 
 /// Manages polls
@@ -20,6 +26,8 @@ pub struct Polls<M: Memory> {
     pending_polls: StableBTreeMap<u64, PendingPoll, M>,
     // Contains the polls that are closed.
     closed_polls: StableBTreeMap<u64, ClosedPoll, M>,
+    /// Maps the ids of the polls moved to an archive canister to the archive canister id
+    archived_polls: StableBTreeMap<u64, Principal, M>,
     /// The next poll id
     polls_id_sequence: StableCell<u64, M>,
 }
@@ -29,6 +37,7 @@ impl<M: Memory> Polls<M> {
         Self {
             pending_polls: StableBTreeMap::new(memory_manager.get(POLLS_PENDING_MAP_MEMORY_ID)),
             closed_polls: StableBTreeMap::new(memory_manager.get(POLLS_CLOSED_MAP_MEMORY_ID)),
+            archived_polls: StableBTreeMap::new(memory_manager.get(POLLS_ARCHIVED_INDEX_MEMORY_ID)),
             polls_id_sequence: StableCell::new(memory_manager.get(POLLS_ID_SEQUENCE_MEMORY_ID), 0)
                 .expect("stable memory POLLS_ID_SEQUENCE_MEMORY_ID initialization failed"),
         }
@@ -44,12 +53,57 @@ impl<M: Memory> Polls<M> {
         self.closed_polls.get(id)
     }
 
-    /// Returns the poll data for the given key
+    /// Returns the poll data for the given key.
+    /// If the poll was moved to an archive canister, the archive canister id is returned.
     pub fn get(&self, id: &u64) -> Option<Poll> {
         self.pending_polls
             .get(id)
             .map(Poll::Pending)
             .or_else(|| self.closed_polls.get(id).map(Poll::Closed))
+            .or_else(|| {
+                self.get_archive_canister(id)
+                    .map(|archive_canister| Poll::Archived { archive_canister })
+            })
+    }
+
+    /// Returns the number of pending polls
+    pub fn pending_count(&self) -> u64 {
+        self.pending_polls.len()
+    }
+
+    /// Returns the number of polls moved to an archive canister
+    pub fn archived_count(&self) -> u64 {
+        self.archived_polls.len()
+    }
+
+    /// Returns the id of the archive canister where the poll was moved
+    pub fn get_archive_canister(&self, id: &u64) -> Option<Principal> {
+        self.archived_polls.get(id)
+    }
+
+    /// Returns up to `limit` closed polls that ended more than `retention_secs` seconds ago
+    pub fn closed_to_archive(
+        &self,
+        timestamp_secs: u64,
+        retention_secs: u64,
+        limit: usize,
+    ) -> Vec<(u64, ClosedPoll)> {
+        self.closed_polls
+            .iter()
+            .filter(|(_, poll)| {
+                poll.end_timestamp_secs.saturating_add(retention_secs) < timestamp_secs
+            })
+            .take(limit)
+            .collect()
+    }
+
+    /// Removes the closed polls moved to the archive canister and keeps track of their location
+    pub fn mark_archived(&mut self, ids: &[u64], archive_canister: Principal) {
+        for id in ids {
+            if self.closed_polls.remove(id).is_some() {
+                self.archived_polls.insert(*id, archive_canister);
+            }
+        }
     }
 
     /// Returns all pending polls
@@ -62,6 +116,20 @@ impl<M: Memory> Polls<M> {
         self.closed_polls.iter().collect()
     }
 
+    /// Returns up to `limit` closed polls starting from the `start` poll id.
+    /// The number of returned polls is capped to `CLOSED_POLLS_MAX_PAGE_SIZE`.
+    pub fn closed_page(&self, start: u64, limit: u64) -> BTreeMap<u64, ClosedPoll> {
+        self.closed_polls
+            .range(start..)
+            .take(limit.min(CLOSED_POLLS_MAX_PAGE_SIZE) as usize)
+            .collect()
+    }
+
+    /// Returns the number of closed polls that are not archived
+    pub fn closed_count(&self) -> u64 {
+        self.closed_polls.len()
+    }
+
     /// Inserts a new poll and returns the generated key
     pub fn insert(&mut self, poll: PollCreateData) -> u64 {
         let id = self.next_id();
@@ -77,20 +145,27 @@ impl<M: Memory> Polls<M> {
         approved: bool,
         timestamp_secs: u64,
     ) -> Result<()> {
-        let mut poll = self.pending_polls.get(&poll_id).ok_or_else(|| {
-            UpgraderError::BadRequest(format!("Poll with id {} not found", poll_id))
-        })?;
+        let Some(mut poll) = self.pending_polls.get(&poll_id) else {
+            return Err(match self.closed_polls.get(&poll_id) {
+                Some(closed) => UpgraderError::PollClosed {
+                    ended_at: closed.end_timestamp_secs,
+                },
+                None => UpgraderError::PollNotFound(poll_id),
+            });
+        };
 
         // Check if the poll is open
         if timestamp_secs < poll.start_timestamp_secs {
-            return Err(UpgraderError::BadRequest(
-                "The poll is not opened yet".to_string(),
-            ));
+            return Err(UpgraderError::PollNotOpen {
+                starts_at: poll.start_timestamp_secs,
+            });
         }
 
         // Check if the poll is closed
         if timestamp_secs > poll.end_timestamp_secs {
-            return Err(UpgraderError::BadRequest("The poll is closed".to_string()));
+            return Err(UpgraderError::PollClosed {
+                ended_at: poll.end_timestamp_secs,
+            });
         }
 
         // Remove the voter from the previous vote
@@ -107,55 +182,199 @@ impl<M: Memory> Polls<M> {
         Ok(())
     }
 
-    /// Finalizes the polls by applying the result and moving them to the closed polls store
+    /// Finalizes the polls whose end timestamp is passed by applying the result
+    /// and moving them to the closed polls store.
+    /// A poll whose action fails is closed as failed with the failure reason
+    /// without affecting the other polls.
+    /// The polls are finalized one at a time while `has_budget` returns true,
+    /// the remaining polls are left pending for a following call.
+    /// Returns the finalized polls.
     pub fn finalize_polls(
         &mut self,
         timestamp_secs: u64,
         permissions_service: &mut Permissions<M>,
-    ) -> Result<()> {
+        settings_service: &mut Settings<M>,
+        projects_service: &mut Projects<M>,
+        mut has_budget: impl FnMut() -> bool,
+    ) -> BTreeMap<u64, ClosedPoll> {
         // loop through all the pending polls and find the closed ones
         let mut polls_to_close = Vec::new();
         for (id, poll) in self.pending_polls.iter() {
             if timestamp_secs > poll.end_timestamp_secs {
-                polls_to_close.push((id, poll.clone()));
+                polls_to_close.push((id, poll));
             }
         }
 
         // close the polls
+        let mut closed_polls = BTreeMap::new();
         for (id, poll) in polls_to_close {
-            let closed_poll = self.close_and_apply_poll(poll, permissions_service)?;
-            self.pending_polls.remove(&id);
-            self.closed_polls.insert(id, closed_poll);
+            if !has_budget() {
+                break;
+            }
+            let closed_poll = self.close_and_store_poll(
+                id,
+                poll,
+                permissions_service,
+                settings_service,
+                projects_service,
+            );
+            closed_polls.insert(id, closed_poll);
         }
 
-        Ok(())
+        closed_polls
     }
 
-    /// Closes the poll and applies the result
+    /// Finalizes a single poll by applying the result and moving it to the closed polls store.
+    /// Returns an error if the poll is not pending or its end timestamp is not passed yet.
+    pub fn finalize_poll(
+        &mut self,
+        id: u64,
+        timestamp_secs: u64,
+        permissions_service: &mut Permissions<M>,
+        settings_service: &mut Settings<M>,
+        projects_service: &mut Projects<M>,
+    ) -> Result<ClosedPoll> {
+        let poll = self
+            .pending_polls
+            .get(&id)
+            .ok_or(UpgraderError::PollNotFound(id))?;
+
+        if timestamp_secs <= poll.end_timestamp_secs {
+            return Err(UpgraderError::PollNotEnded {
+                ends_at: poll.end_timestamp_secs,
+            });
+        }
+
+        Ok(self.close_and_store_poll(
+            id,
+            poll,
+            permissions_service,
+            settings_service,
+            projects_service,
+        ))
+    }
+
+    /// Returns the project hashes approved by the accepted polls that are not archived,
+    /// as (poll id, project, hash) sorted by poll id
+    pub fn accepted_project_hashes(&self) -> Vec<(u64, String, String)> {
+        let mut hashes = Vec::new();
+        for (id, poll) in self.closed_polls.iter() {
+            if poll.result != PollResult::Accepted {
+                continue;
+            }
+            match poll.poll_type {
+                PollType::ProjectHash { project, hash } => hashes.push((id, project, hash)),
+                PollType::Batch(actions) => {
+                    for action in actions {
+                        if let PollAction::ProjectHash { project, hash } = action {
+                            hashes.push((id, project, hash));
+                        }
+                    }
+                }
+                _ => (),
+            }
+        }
+        hashes
+    }
+
+    /// Returns true if any pending poll satisfies the predicate
+    pub fn any_pending(&self, predicate: impl Fn(&PendingPoll) -> bool) -> bool {
+        self.pending_polls.iter().any(|(_, poll)| predicate(&poll))
+    }
+
+    /// Returns true if there are pending polls whose end timestamp is passed
+    pub fn has_polls_to_finalize(&self, timestamp_secs: u64) -> bool {
+        self.pending_polls
+            .iter()
+            .any(|(_, poll)| timestamp_secs > poll.end_timestamp_secs)
+    }
+
+    /// Closes the poll, applies the result and moves it to the closed polls store.
+    /// If the result cannot be applied, the poll is closed as failed with the failure reason.
+    fn close_and_store_poll(
+        &mut self,
+        id: u64,
+        poll: PendingPoll,
+        permissions_service: &mut Permissions<M>,
+        settings_service: &mut Settings<M>,
+        projects_service: &mut Projects<M>,
+    ) -> ClosedPoll {
+        let closed_poll = match self.close_and_apply_poll(
+            id,
+            poll.clone(),
+            permissions_service,
+            settings_service,
+            projects_service,
+        ) {
+            Ok(closed_poll) => closed_poll,
+            Err(err) => {
+                warn!("Failed to apply the result of the poll {id}: {err}");
+                poll.close(PollResult::Failed(err.to_string()))
+            }
+        };
+        self.pending_polls.remove(&id);
+        self.closed_polls.insert(id, closed_poll.clone());
+        closed_poll
+    }
+
+    /// Closes the poll and applies the result.
+    /// The poll is accepted if it reaches the quorum and has more yes votes than no votes.
     fn close_and_apply_poll(
         &mut self,
+        id: u64,
         poll: PendingPoll,
         permissions_service: &mut Permissions<M>,
+        settings_service: &mut Settings<M>,
+        projects_service: &mut Projects<M>,
     ) -> Result<ClosedPoll> {
-        if poll.yes_voters.len() > poll.no_voters.len() {
+        let quorum = settings_service.poll_settings().quorum;
+        let votes = poll.yes_votes() + poll.no_votes();
+        if votes >= quorum && poll.yes_voters.len() > poll.no_voters.len() {
             match &poll.poll_type {
-                upgrader_canister_did::PollType::AddPermission {
+                PollType::AddPermission {
                     principals,
                     permissions,
                 } => {
-                    for principal in principals {
-                        permissions_service.add_permissions(*principal, permissions.clone())?;
-                    }
+                    Self::check_principals(principals, permissions_service)?;
+                    Self::add_permissions(principals, permissions, permissions_service)?;
                 }
-                upgrader_canister_did::PollType::RemovePermission {
+                PollType::RemovePermission {
                     principals,
                     permissions,
                 } => {
-                    for principal in principals {
-                        permissions_service.remove_permissions(*principal, permissions)?;
+                    Self::check_principals(principals, permissions_service)?;
+                    settings_service.check_admins_count(
+                        permissions_service.admins_count_after_removal(principals, permissions),
+                    )?;
+                    Self::remove_permissions(principals, permissions, permissions_service)?;
+                }
+                PollType::ProjectHash { project, hash } => {
+                    projects_service.approve_hash(project, hash, id)?
+                }
+                PollType::RevokeProjectHash {
+                    project,
+                    hash,
+                    reason,
+                } => projects_service.revoke_hash(project, hash, Some(id), reason.clone())?,
+                PollType::ReapproveProjectHash { project, hash } => {
+                    projects_service.reapprove_hash(project, hash, id)?
+                }
+                PollType::UpdateSettings(patch) => settings_service.apply_patch(patch)?,
+                PollType::CreateProject(project) => projects_service.insert(project.clone())?,
+                PollType::UpdateProject(project) => projects_service.update(project.clone())?,
+                PollType::Batch(actions) => {
+                    // All the actions are checked before applying any of them
+                    for action in actions {
+                        Self::check_action(action, permissions_service, projects_service)?;
                     }
+                    Self::apply_batch(
+                        id,
+                        actions,
+                        permissions_service,
+                        settings_service,
+                        projects_service,
+                    )?;
                 }
-                upgrader_canister_did::PollType::ProjectHash { .. } => (),
             }
             Ok(poll.close(PollResult::Accepted))
         } else {
@@ -163,6 +382,134 @@ impl<M: Memory> Polls<M> {
         }
     }
 
+    /// Checks that the action of a batch poll can be applied
+    fn check_action(
+        action: &PollAction,
+        permissions_service: &Permissions<M>,
+        projects_service: &Projects<M>,
+    ) -> Result<()> {
+        match action {
+            PollAction::AddPermission { principals, .. }
+            | PollAction::RemovePermission { principals, .. } => {
+                Self::check_principals(principals, permissions_service)?;
+            }
+            PollAction::ProjectHash { project, hash } => {
+                projects_service
+                    .get(project)
+                    .ok_or_else(|| UpgraderError::ProjectNotFound(project.clone()))?;
+                projects_service.check_hash_not_revoked(project, hash)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies all the actions of a batch poll atomically:
+    /// if an action fails, or if the admins left are fewer than the multi-admin approval
+    /// threshold, the permissions and project hashes changed by the previous actions
+    /// are restored and the error is returned
+    fn apply_batch(
+        poll_id: u64,
+        actions: &[PollAction],
+        permissions_service: &mut Permissions<M>,
+        settings_service: &Settings<M>,
+        projects_service: &mut Projects<M>,
+    ) -> Result<()> {
+        let mut saved_permissions = BTreeMap::new();
+        let mut saved_hashes = BTreeMap::new();
+        for action in actions {
+            match action {
+                PollAction::AddPermission { principals, .. }
+                | PollAction::RemovePermission { principals, .. } => {
+                    for principal in principals {
+                        saved_permissions
+                            .entry(*principal)
+                            .or_insert_with(|| permissions_service.get_permissions(principal));
+                    }
+                }
+                PollAction::ProjectHash { project, .. } => {
+                    saved_hashes
+                        .entry(project.clone())
+                        .or_insert_with(|| projects_service.hashes(project));
+                }
+            }
+        }
+
+        let applied = actions
+            .iter()
+            .try_for_each(|action| {
+                Self::apply_action(poll_id, action, permissions_service, projects_service)
+            })
+            .and_then(|()| {
+                settings_service
+                    .check_admins_count(permissions_service.principals_count(&Permission::Admin))
+            });
+        if applied.is_err() {
+            for (principal, permissions) in saved_permissions {
+                permissions_service.set_permissions(principal, permissions);
+            }
+            for (project, hashes) in saved_hashes {
+                projects_service.set_hashes(&project, hashes);
+            }
+        }
+        applied
+    }
+
+    /// Applies the action of a batch poll
+    fn apply_action(
+        poll_id: u64,
+        action: &PollAction,
+        permissions_service: &mut Permissions<M>,
+        projects_service: &mut Projects<M>,
+    ) -> Result<()> {
+        match action {
+            PollAction::AddPermission {
+                principals,
+                permissions,
+            } => Self::add_permissions(principals, permissions, permissions_service),
+            PollAction::RemovePermission {
+                principals,
+                permissions,
+            } => Self::remove_permissions(principals, permissions, permissions_service),
+            PollAction::ProjectHash { project, hash } => {
+                projects_service.approve_hash(project, hash, poll_id)
+            }
+        }
+    }
+
+    /// Checks that the permissions of all the principals can be changed,
+    /// so that a permission poll is applied to all of them or to none
+    fn check_principals(
+        principals: &[Principal],
+        permissions_service: &Permissions<M>,
+    ) -> Result<()> {
+        for principal in principals {
+            permissions_service.check_anonymous_principal(principal)?;
+        }
+        Ok(())
+    }
+
+    fn add_permissions(
+        principals: &[Principal],
+        permissions: &[Permission],
+        permissions_service: &mut Permissions<M>,
+    ) -> Result<()> {
+        for principal in principals {
+            permissions_service.add_permissions(*principal, permissions.to_vec())?;
+        }
+        Ok(())
+    }
+
+    fn remove_permissions(
+        principals: &[Principal],
+        permissions: &[Permission],
+        permissions_service: &mut Permissions<M>,
+    ) -> Result<()> {
+        for principal in principals {
+            permissions_service.remove_permissions(*principal, permissions)?;
+        }
+        Ok(())
+    }
+
     /// Returns the next poll id
     fn next_id(&mut self) -> u64 {
         // Polls could be removed from the map so we need to keep track of the next id
@@ -180,7 +527,10 @@ mod test {
     use std::collections::HashSet;
 
     use candid::Principal;
-    use upgrader_canister_did::{Permission, PollResult, PollType};
+    use upgrader_canister_did::{
+        AdminApprovalSettings, Permission, Poll, PollAction, PollResult, PollType, ProjectData,
+        ProjectHashStatus, SettingsPatch, UpgraderError,
+    };
 
     /// Verifies that the next id is generated correctly
     #[test]
@@ -237,7 +587,7 @@ mod test {
         let result = polls.vote(0, candid::Principal::anonymous(), true, 0);
 
         // Assert
-        assert!(result.is_err());
+        assert_eq!(result, Err(UpgraderError::PollNotFound(0)));
     }
 
     /// Should vote for a poll
@@ -340,8 +690,14 @@ mod test {
         assert!(polls.vote(poll_id, principal_1, true, 0).is_ok());
         assert!(polls.vote(poll_id, principal_1, true, end_ts - 1).is_ok());
         assert!(polls.vote(poll_id, principal_1, true, end_ts).is_ok());
-        assert!(polls.vote(poll_id, principal_1, true, end_ts + 1).is_err());
-        assert!(polls.vote(poll_id, principal_1, true, u64::MAX).is_err());
+        assert_eq!(
+            polls.vote(poll_id, principal_1, true, end_ts + 1),
+            Err(UpgraderError::PollClosed { ended_at: end_ts })
+        );
+        assert_eq!(
+            polls.vote(poll_id, principal_1, true, u64::MAX),
+            Err(UpgraderError::PollClosed { ended_at: end_ts })
+        );
     }
 
     /// Should return an error if the poll is opened
@@ -368,10 +724,18 @@ mod test {
         // Act & Assert
         assert!(polls.vote(poll_id, principal_1, true, start_ts).is_ok());
         assert!(polls.vote(poll_id, principal_1, true, start_ts + 1).is_ok());
-        assert!(polls
-            .vote(poll_id, principal_1, true, start_ts - 1)
-            .is_err());
-        assert!(polls.vote(poll_id, principal_1, true, 0).is_err());
+        assert_eq!(
+            polls.vote(poll_id, principal_1, true, start_ts - 1),
+            Err(UpgraderError::PollNotOpen {
+                starts_at: start_ts
+            })
+        );
+        assert_eq!(
+            polls.vote(poll_id, principal_1, true, 0),
+            Err(UpgraderError::PollNotOpen {
+                starts_at: start_ts
+            })
+        );
     }
 
     /// Should had the permissions if the poll is approved
@@ -381,6 +745,8 @@ mod test {
         let memory_manager = ic_stable_structures::default_ic_memory_manager();
         let mut polls = super::Polls::new(&memory_manager);
         let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
 
         let principal_1 = Principal::from_slice(&[1, 29]);
         let principal_2 = Principal::from_slice(&[2, 29]);
@@ -399,7 +765,9 @@ mod test {
         };
 
         // Act
-        let closed_poll = polls.close_and_apply_poll(poll, &mut permissions).unwrap();
+        let closed_poll = polls
+            .close_and_apply_poll(0, poll, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
 
         // Assert
         assert_eq!(closed_poll.result, PollResult::Accepted);
@@ -424,6 +792,8 @@ mod test {
         let memory_manager = ic_stable_structures::default_ic_memory_manager();
         let mut polls = super::Polls::new(&memory_manager);
         let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
 
         let principal_1 = Principal::from_slice(&[1, 29]);
         let principal_2 = Principal::from_slice(&[2, 29]);
@@ -442,7 +812,9 @@ mod test {
         };
 
         // Act
-        let closed_poll = polls.close_and_apply_poll(poll, &mut permissions).unwrap();
+        let closed_poll = polls
+            .close_and_apply_poll(0, poll, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
 
         // Assert
         assert_eq!(closed_poll.result, PollResult::Rejected);
@@ -460,6 +832,70 @@ mod test {
         );
     }
 
+    /// Should fail a poll removing the admins below the multi-admin approval threshold
+    #[test]
+    fn test_process_poll_remove_admins_below_the_approval_threshold() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let principal_2 = Principal::from_slice(&[2, 29]);
+        for principal in [principal_1, principal_2] {
+            permissions
+                .add_permissions(principal, vec![Permission::Admin])
+                .unwrap();
+        }
+        settings
+            .set_admin_approval(
+                Some(AdminApprovalSettings {
+                    threshold: 2,
+                    expiry_secs: 3600,
+                }),
+                2,
+            )
+            .unwrap();
+
+        let remove_admin = |poll_type: PollType| upgrader_canister_did::PendingPoll {
+            description: "poll_0".to_string(),
+            poll_type,
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 234567,
+            yes_voters: vec![principal_1],
+            no_voters: vec![],
+        };
+
+        // Act
+        let closed_poll = polls.close_and_store_poll(
+            0,
+            remove_admin(PollType::RemovePermission {
+                principals: vec![principal_2],
+                permissions: vec![Permission::Admin],
+            }),
+            &mut permissions,
+            &mut settings,
+            &mut projects,
+        );
+        let closed_batch_poll = polls.close_and_store_poll(
+            1,
+            remove_admin(PollType::Batch(vec![PollAction::RemovePermission {
+                principals: vec![principal_2],
+                permissions: vec![Permission::Admin],
+            }])),
+            &mut permissions,
+            &mut settings,
+            &mut projects,
+        );
+
+        // Assert
+        assert!(matches!(closed_poll.result, PollResult::Failed(_)));
+        assert!(matches!(closed_batch_poll.result, PollResult::Failed(_)));
+        assert_eq!(permissions.principals_count(&Permission::Admin), 2);
+    }
+
     /// should remove the permissions if the poll approved
     #[test]
     fn test_process_poll_remove_permission() {
@@ -467,6 +903,8 @@ mod test {
         let memory_manager = ic_stable_structures::default_ic_memory_manager();
         let mut polls = super::Polls::new(&memory_manager);
         let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
 
         let principal_1 = Principal::from_slice(&[1, 29]);
         let principal_2 = Principal::from_slice(&[2, 29]);
@@ -509,7 +947,9 @@ mod test {
         };
 
         // Act
-        let closed_poll = polls.close_and_apply_poll(poll, &mut permissions).unwrap();
+        let closed_poll = polls
+            .close_and_apply_poll(0, poll, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
 
         // Assert
         assert_eq!(closed_poll.result, PollResult::Accepted);
@@ -538,6 +978,8 @@ mod test {
         let memory_manager = ic_stable_structures::default_ic_memory_manager();
         let mut polls = super::Polls::new(&memory_manager);
         let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
 
         let principal_1 = Principal::from_slice(&[1, 29]);
         let principal_2 = Principal::from_slice(&[2, 29]);
@@ -580,7 +1022,9 @@ mod test {
         };
 
         // Act
-        let closed_poll = polls.close_and_apply_poll(poll, &mut permissions).unwrap();
+        let closed_poll = polls
+            .close_and_apply_poll(0, poll, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
 
         // Assert
         assert_eq!(closed_poll.result, PollResult::Rejected);
@@ -606,6 +1050,54 @@ mod test {
         );
     }
 
+    /// Should move the closed polls older than the retention window to the archive index
+    #[test]
+    fn test_archive_closed_polls() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+        let archive_canister = Principal::from_slice(&[10; 29]);
+
+        let mut ids = vec![];
+        for end_timestamp_secs in [10, 20, 30] {
+            ids.push(polls.insert(upgrader_canister_did::PollCreateData {
+                description: "poll".to_string(),
+                poll_type: PollType::ProjectHash {
+                    project: "project".to_owned(),
+                    hash: "hash".to_owned(),
+                },
+                start_timestamp_secs: 0,
+                end_timestamp_secs,
+            }));
+        }
+        polls.finalize_polls(100, &mut permissions, &mut settings, &mut projects, || true);
+
+        // Act
+        let to_archive = polls.closed_to_archive(100, 75, 10);
+
+        // Assert
+        assert_eq!(
+            to_archive.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![ids[0], ids[1]]
+        );
+        assert_eq!(polls.closed_to_archive(100, 75, 1).len(), 1);
+
+        polls.mark_archived(&[ids[0], ids[1]], archive_canister);
+
+        assert_eq!(polls.get_closed(&ids[0]), None);
+        assert_eq!(polls.get_archive_canister(&ids[0]), Some(archive_canister));
+        assert_eq!(
+            polls.get(&ids[1]),
+            Some(Poll::Archived { archive_canister })
+        );
+        assert_eq!(polls.get_archive_canister(&ids[1]), Some(archive_canister));
+        assert!(matches!(polls.get(&ids[2]), Some(Poll::Closed(_))));
+        assert!(polls.closed_to_archive(100, 75, 10).is_empty());
+    }
+
     /// Should finalize the polls and move them to closed polls
     #[test]
     fn test_finalize_polls() {
@@ -613,6 +1105,8 @@ mod test {
         let memory_manager = ic_stable_structures::default_ic_memory_manager();
         let mut polls = super::Polls::new(&memory_manager);
         let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
 
         let principal_1 = Principal::from_slice(&[1, 29]);
         let principal_2 = Principal::from_slice(&[2, 29]);
@@ -661,7 +1155,8 @@ mod test {
         polls.vote(poll_2_id, principal_3, false, 0).unwrap();
 
         // Act
-        polls.finalize_polls(3, &mut permissions).unwrap();
+        let closed_polls =
+            polls.finalize_polls(3, &mut permissions, &mut settings, &mut projects, || true);
 
         // Assert
         assert_eq!(polls.get_pending(&poll_0_id), None);
@@ -673,10 +1168,571 @@ mod test {
         assert_eq!(polls.get_pending(&poll_2_id).unwrap().description, "poll_2");
         assert_eq!(polls.get_closed(&poll_2_id), None);
 
+        assert_eq!(
+            closed_polls.keys().copied().collect::<Vec<_>>(),
+            vec![poll_0_id, poll_1_id]
+        );
+
         // The permissions should be added because the poll_0 was approved
         assert_eq!(
             permissions.get_permissions(&principal_1).permissions,
             HashSet::from([Permission::Admin])
         );
     }
+
+    /// Should finalize a single poll only after its end timestamp
+    #[test]
+    fn test_finalize_poll() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::AddPermission {
+                principals: vec![principal_1],
+                permissions: vec![Permission::VotePoll],
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 10,
+        });
+        polls.vote(poll_id, principal_1, true, 0).unwrap();
+
+        // Act & Assert
+        assert_eq!(
+            polls.finalize_poll(poll_id, 10, &mut permissions, &mut settings, &mut projects),
+            Err(UpgraderError::PollNotEnded { ends_at: 10 })
+        );
+        assert!(polls.get_pending(&poll_id).is_some());
+
+        let closed_poll = polls
+            .finalize_poll(poll_id, 11, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
+        assert_eq!(closed_poll.result, PollResult::Accepted);
+        assert_eq!(polls.get_closed(&poll_id), Some(closed_poll));
+        assert_eq!(polls.get_pending(&poll_id), None);
+        assert_eq!(
+            permissions.get_permissions(&principal_1).permissions,
+            HashSet::from([Permission::VotePoll])
+        );
+
+        // A closed poll cannot be finalized again
+        assert!(polls
+            .finalize_poll(poll_id, 12, &mut permissions, &mut settings, &mut projects)
+            .is_err());
+        assert_eq!(
+            polls.finalize_poll(100, 12, &mut permissions, &mut settings, &mut projects),
+            Err(UpgraderError::PollNotFound(100))
+        );
+
+        // A closed poll cannot be voted
+        assert_eq!(
+            polls.vote(poll_id, principal_1, true, 12),
+            Err(UpgraderError::PollClosed { ended_at: 10 })
+        );
+    }
+
+    /// Should close a poll whose action fails with the failed result
+    /// without blocking the finalization of the other polls
+    #[test]
+    fn test_finalize_polls_isolates_failures() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let failing_poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::AddPermission {
+                principals: vec![Principal::anonymous()],
+                permissions: vec![Permission::Admin],
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        let poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_1".to_string(),
+            poll_type: PollType::AddPermission {
+                principals: vec![principal_1],
+                permissions: vec![Permission::VotePoll],
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        polls.vote(failing_poll_id, principal_1, true, 0).unwrap();
+        polls.vote(poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        let closed_polls =
+            polls.finalize_polls(2, &mut permissions, &mut settings, &mut projects, || true);
+
+        // Assert
+        assert_eq!(closed_polls.len(), 2);
+        let failing_poll = polls.get_closed(&failing_poll_id).unwrap();
+        assert!(matches!(failing_poll.result, PollResult::Failed(_)));
+        let poll = polls.get_closed(&poll_id).unwrap();
+        assert_eq!(poll.result, PollResult::Accepted);
+        assert_eq!(
+            permissions.get_permissions(&principal_1).permissions,
+            HashSet::from([Permission::VotePoll])
+        );
+    }
+
+    /// Should stop the finalization when the budget is exhausted and resume it on the next call
+    #[test]
+    fn test_finalize_polls_resumes_when_budget_is_exhausted() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        for i in 0..5 {
+            polls.insert(upgrader_canister_did::PollCreateData {
+                description: format!("poll_{i}"),
+                poll_type: PollType::ProjectHash {
+                    project: "project".to_owned(),
+                    hash: "hash".to_owned(),
+                },
+                start_timestamp_secs: 0,
+                end_timestamp_secs: 1,
+            });
+        }
+
+        // Act
+        let mut budget = 3;
+        let first_batch =
+            polls.finalize_polls(2, &mut permissions, &mut settings, &mut projects, || {
+                budget -= 1;
+                budget >= 0
+            });
+        let has_polls_to_finalize = polls.has_polls_to_finalize(2);
+        let second_batch =
+            polls.finalize_polls(2, &mut permissions, &mut settings, &mut projects, || true);
+
+        // Assert
+        assert_eq!(
+            first_batch.keys().copied().collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert!(has_polls_to_finalize);
+        assert_eq!(second_batch.keys().copied().collect::<Vec<_>>(), vec![3, 4]);
+        assert!(!polls.has_polls_to_finalize(2));
+        assert_eq!(polls.pending_count(), 0);
+    }
+
+    /// Should apply all the actions of an accepted batch poll
+    #[test]
+    fn test_process_poll_batch() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+        projects
+            .insert(ProjectData {
+                key: "project".to_string(),
+                name: "Project".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let principal_2 = Principal::from_slice(&[2, 29]);
+        permissions
+            .add_permissions(principal_2, vec![Permission::CreatePoll])
+            .unwrap();
+
+        let poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::Batch(vec![
+                PollAction::AddPermission {
+                    principals: vec![principal_1],
+                    permissions: vec![Permission::VotePoll],
+                },
+                PollAction::RemovePermission {
+                    principals: vec![principal_2],
+                    permissions: vec![Permission::CreatePoll],
+                },
+                PollAction::ProjectHash {
+                    project: "project".to_owned(),
+                    hash: "hash".to_owned(),
+                },
+            ]),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        polls.vote(poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        let closed_poll = polls
+            .finalize_poll(poll_id, 2, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
+
+        // Assert
+        assert_eq!(closed_poll.result, PollResult::Accepted);
+        assert_eq!(
+            permissions.get_permissions(&principal_1).permissions,
+            HashSet::from([Permission::VotePoll])
+        );
+        assert!(permissions
+            .get_permissions(&principal_2)
+            .permissions
+            .is_empty());
+    }
+
+    /// Should change the permissions of none of the principals of a poll if one of them is invalid
+    #[test]
+    fn test_process_poll_permissions_is_atomic() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::AddPermission {
+                principals: vec![principal_1, Principal::anonymous()],
+                permissions: vec![Permission::VotePoll],
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        polls.vote(poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        let closed_poll = polls
+            .finalize_poll(poll_id, 2, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
+
+        // Assert
+        assert_eq!(
+            closed_poll.result,
+            PollResult::Failed(UpgraderError::AnonymousPrincipalNotAllowed.to_string())
+        );
+        assert!(permissions
+            .get_permissions(&principal_1)
+            .permissions
+            .is_empty());
+    }
+
+    /// Should apply none of the actions of a batch poll if one of them fails
+    #[test]
+    fn test_process_poll_batch_is_atomic() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::Batch(vec![
+                PollAction::AddPermission {
+                    principals: vec![principal_1],
+                    permissions: vec![Permission::VotePoll],
+                },
+                PollAction::AddPermission {
+                    principals: vec![Principal::anonymous()],
+                    permissions: vec![Permission::VotePoll],
+                },
+            ]),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        polls.vote(poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        let closed_poll = polls
+            .finalize_poll(poll_id, 2, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
+
+        // Assert
+        assert!(matches!(closed_poll.result, PollResult::Failed(_)));
+        assert!(permissions
+            .get_permissions(&principal_1)
+            .permissions
+            .is_empty());
+    }
+
+    /// Should restore the changes of the previous actions of a batch
+    /// if an action fails when it is applied
+    #[test]
+    fn test_apply_batch_restores_the_state_when_a_later_action_fails() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let principal_2 = Principal::from_slice(&[2, 29]);
+        permissions
+            .add_permissions(principal_2, vec![Permission::VotePoll])
+            .unwrap();
+        projects
+            .insert(ProjectData {
+                key: "project".to_string(),
+                name: "project".to_string(),
+                description: "project".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+        let actions = vec![
+            PollAction::AddPermission {
+                principals: vec![principal_1],
+                permissions: vec![Permission::CreatePoll],
+            },
+            PollAction::RemovePermission {
+                principals: vec![principal_2],
+                permissions: vec![Permission::VotePoll],
+            },
+            PollAction::ProjectHash {
+                project: "project".to_string(),
+                hash: "hash".to_string(),
+            },
+            PollAction::ProjectHash {
+                project: "missing".to_string(),
+                hash: "hash".to_string(),
+            },
+        ];
+
+        // Act
+        let result =
+            super::Polls::apply_batch(0, &actions, &mut permissions, &settings, &mut projects);
+
+        // Assert
+        assert_eq!(
+            result,
+            Err(UpgraderError::ProjectNotFound("missing".to_string()))
+        );
+        assert!(permissions
+            .get_permissions(&principal_1)
+            .permissions
+            .is_empty());
+        assert_eq!(
+            permissions.get_permissions(&principal_2).permissions,
+            HashSet::from([Permission::VotePoll])
+        );
+        assert!(projects.hashes(&"project".to_string()).is_empty());
+        assert!(projects.has_no_hashes());
+    }
+
+    /// Should reject an approved poll that does not reach the quorum
+    #[test]
+    fn test_process_poll_quorum() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+        projects
+            .insert(ProjectData {
+                key: "project".to_string(),
+                name: "Project".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_quorum: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let principal_2 = Principal::from_slice(&[2, 29]);
+        let mut ids = vec![];
+        for _ in 0..2 {
+            ids.push(polls.insert(upgrader_canister_did::PollCreateData {
+                description: "poll".to_string(),
+                poll_type: PollType::ProjectHash {
+                    project: "project".to_owned(),
+                    hash: "hash".to_owned(),
+                },
+                start_timestamp_secs: 0,
+                end_timestamp_secs: 1,
+            }));
+        }
+        polls.vote(ids[0], principal_1, true, 0).unwrap();
+        polls.vote(ids[1], principal_1, true, 0).unwrap();
+        polls.vote(ids[1], principal_2, true, 0).unwrap();
+
+        // Act
+        let closed_polls =
+            polls.finalize_polls(2, &mut permissions, &mut settings, &mut projects, || true);
+
+        // Assert
+        assert_eq!(closed_polls[&ids[0]].result, PollResult::Rejected);
+        assert_eq!(closed_polls[&ids[1]].result, PollResult::Accepted);
+    }
+
+    /// Should apply the settings patch of an accepted poll
+    #[test]
+    fn test_process_poll_update_settings() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll".to_string(),
+            poll_type: PollType::UpdateSettings(SettingsPatch {
+                disable_inspect_message: Some(true),
+                poll_quorum: Some(5),
+                ..Default::default()
+            }),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        polls.vote(poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        let closed_poll = polls
+            .finalize_poll(poll_id, 2, &mut permissions, &mut settings, &mut projects)
+            .unwrap();
+
+        // Assert
+        assert_eq!(closed_poll.result, PollResult::Accepted);
+        assert!(settings.is_inspect_message_disabled());
+        assert_eq!(settings.poll_settings().quorum, 5);
+    }
+
+    /// Should create and update the projects of the accepted polls
+    #[test]
+    fn test_process_poll_create_and_update_project() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let project = ProjectData {
+            key: "key".to_string(),
+            name: "Project".to_string(),
+            description: "Description".to_string(),
+            canisters: None,
+        };
+        let updated_project = ProjectData {
+            description: "New description".to_string(),
+            ..project.clone()
+        };
+
+        let create_poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::CreateProject(project.clone()),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        let update_poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_1".to_string(),
+            poll_type: PollType::UpdateProject(updated_project.clone()),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 2,
+        });
+        polls.vote(create_poll_id, principal_1, true, 0).unwrap();
+        polls.vote(update_poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        polls.finalize_polls(2, &mut permissions, &mut settings, &mut projects, || true);
+        let project_after_create = projects.get(&project.key);
+        polls.finalize_polls(3, &mut permissions, &mut settings, &mut projects, || true);
+
+        // Assert
+        assert_eq!(project_after_create, Some(project.clone()));
+        assert_eq!(projects.get(&project.key), Some(updated_project));
+        assert_eq!(
+            polls.get_closed(&update_poll_id).unwrap().result,
+            PollResult::Accepted
+        );
+    }
+
+    /// Should index the hashes approved by the accepted polls and revoke them
+    #[test]
+    fn test_process_poll_approve_and_revoke_project_hash() {
+        // Arrange
+        let memory_manager = ic_stable_structures::default_ic_memory_manager();
+        let mut polls = super::Polls::new(&memory_manager);
+        let mut permissions = super::Permissions::new(&memory_manager);
+        let mut settings = super::Settings::new(&memory_manager);
+        let mut projects = super::Projects::new(&memory_manager);
+        let project_key = "project".to_string();
+        projects
+            .insert(ProjectData {
+                key: project_key.clone(),
+                name: "Project".to_string(),
+                description: "Description".to_string(),
+                canisters: None,
+            })
+            .unwrap();
+
+        let principal_1 = Principal::from_slice(&[1, 29]);
+        let approve_poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_0".to_string(),
+            poll_type: PollType::ProjectHash {
+                project: project_key.clone(),
+                hash: "hash".to_owned(),
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 1,
+        });
+        let revoke_poll_id = polls.insert(upgrader_canister_did::PollCreateData {
+            description: "poll_1".to_string(),
+            poll_type: PollType::RevokeProjectHash {
+                project: project_key.clone(),
+                hash: "hash".to_owned(),
+                reason: "vulnerability".to_owned(),
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 2,
+        });
+        polls.vote(approve_poll_id, principal_1, true, 0).unwrap();
+        polls.vote(revoke_poll_id, principal_1, true, 0).unwrap();
+
+        // Act
+        polls.finalize_polls(2, &mut permissions, &mut settings, &mut projects, || true);
+        let status_after_approval = projects.hash_status(&project_key, "hash");
+        polls.finalize_polls(3, &mut permissions, &mut settings, &mut projects, || true);
+
+        // Assert
+        assert_eq!(
+            status_after_approval,
+            Some(ProjectHashStatus::Approved {
+                poll_id: approve_poll_id
+            })
+        );
+        assert_eq!(
+            projects.hash_status(&project_key, "hash"),
+            Some(ProjectHashStatus::Revoked {
+                poll_id: Some(revoke_poll_id),
+                reason: "vulnerability".to_string()
+            })
+        );
+        assert_eq!(
+            polls.accepted_project_hashes(),
+            vec![(approve_poll_id, project_key, "hash".to_string())]
+        );
+    }
 }
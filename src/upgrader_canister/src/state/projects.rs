@@ -1,19 +1,27 @@
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use candid::{CandidType, Deserialize};
 use ic_stable_structures::stable_structures::Memory;
-use ic_stable_structures::{BTreeMapStructure, MemoryManager, StableBTreeMap};
+use ic_stable_structures::{BTreeMapStructure, Bound, MemoryManager, StableBTreeMap, Storable};
+use serde::Serialize;
 use upgrader_canister_did::error::{Result, UpgraderError};
-use upgrader_canister_did::ProjectData;
+use upgrader_canister_did::{codec, DeploymentStatus, ProjectData, ProjectHashStatus, WasmHash};
 
-use crate::constant::PROJECTS_MAP_MEMORY_ID;
+use crate::constant::{PROJECTS_MAP_MEMORY_ID, PROJECT_HASHES_MAP_MEMORY_ID};
 
 /// Manages available projects
 pub struct Projects<M: Memory> {
     projects: StableBTreeMap<String, ProjectData, M>,
+    /// The status of the approved and revoked hashes by project key
+    hashes: StableBTreeMap<String, ProjectHashes, M>,
 }
 
 impl<M: Memory> Projects<M> {
     pub fn new(memory_manager: &dyn MemoryManager<M, u8>) -> Self {
         Self {
             projects: StableBTreeMap::new(memory_manager.get(PROJECTS_MAP_MEMORY_ID)),
+            hashes: StableBTreeMap::new(memory_manager.get(PROJECT_HASHES_MAP_MEMORY_ID)),
         }
     }
 
@@ -37,6 +45,183 @@ impl<M: Memory> Projects<M> {
             Ok(())
         }
     }
+
+    /// Updates the project data for the given key
+    /// Returns an error if the key does not exist
+    pub fn update(&mut self, project: ProjectData) -> Result<()> {
+        if self.projects.contains_key(&project.key) {
+            self.projects.insert(project.key.clone(), project);
+            Ok(())
+        } else {
+            Err(UpgraderError::ProjectNotFound(project.key))
+        }
+    }
+
+    /// Marks the hash of the project as approved by the given poll
+    pub fn approve_hash(&mut self, project: &String, hash: &str, poll_id: u64) -> Result<()> {
+        self.check_project_exists(project)?;
+        self.check_hash_not_revoked(project, hash)?;
+        self.update_hashes(project, |hashes| {
+            hashes.insert(hash.to_string(), ProjectHashStatus::Approved { poll_id });
+        });
+        Ok(())
+    }
+
+    /// Marks a revoked hash of the project as approved again by the given poll.
+    /// Returns an error if the hash is not revoked.
+    pub fn reapprove_hash(&mut self, project: &String, hash: &str, poll_id: u64) -> Result<()> {
+        self.check_hash_revoked(project, hash)?;
+        self.update_hashes(project, |hashes| {
+            hashes.insert(hash.to_string(), ProjectHashStatus::Approved { poll_id });
+        });
+        Ok(())
+    }
+
+    /// Marks an approved hash of the project as revoked.
+    /// `poll_id` is the id of the revoking poll, or `None` if the hash is revoked by an admin.
+    /// Returns an error if the hash is not approved.
+    pub fn revoke_hash(
+        &mut self,
+        project: &String,
+        hash: &str,
+        poll_id: Option<u64>,
+        reason: String,
+    ) -> Result<()> {
+        self.check_hash_approved(project, hash)?;
+        self.update_hashes(project, |hashes| {
+            hashes.insert(
+                hash.to_string(),
+                ProjectHashStatus::Revoked { poll_id, reason },
+            );
+        });
+        Ok(())
+    }
+
+    /// Returns an error if the hash of the project is not approved
+    pub fn check_hash_approved(&self, project: &String, hash: &str) -> Result<()> {
+        match self.hash_status(project, hash) {
+            Some(ProjectHashStatus::Approved { .. }) => Ok(()),
+            _ => Err(UpgraderError::BadRequest(format!(
+                "The hash [{hash}] of the project [{project}] is not approved"
+            ))),
+        }
+    }
+
+    /// Returns an error if the hash of the project is revoked
+    pub fn check_hash_not_revoked(&self, project: &String, hash: &str) -> Result<()> {
+        match self.hash_status(project, hash) {
+            Some(ProjectHashStatus::Revoked { .. }) => Err(UpgraderError::ProjectHashRevoked {
+                project: project.clone(),
+                hash: hash.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns an error if the hash of the project is not revoked
+    pub fn check_hash_revoked(&self, project: &String, hash: &str) -> Result<()> {
+        match self.hash_status(project, hash) {
+            Some(ProjectHashStatus::Revoked { .. }) => Ok(()),
+            _ => Err(UpgraderError::BadRequest(format!(
+                "The hash [{hash}] of the project [{project}] is not revoked"
+            ))),
+        }
+    }
+
+    /// Returns the status of the hash of the project, if it was ever approved
+    pub fn hash_status(&self, project: &String, hash: &str) -> Option<ProjectHashStatus> {
+        self.hashes
+            .get(project)
+            .and_then(|hashes| hashes.0.get(hash).cloned())
+    }
+
+    /// Returns the status of all the approved and revoked hashes of the project
+    pub fn hashes(&self, project: &String) -> BTreeMap<String, ProjectHashStatus> {
+        self.hashes.get(project).unwrap_or_default().0
+    }
+
+    /// Replaces the status of all the approved and revoked hashes of the project
+    pub fn set_hashes(&mut self, project: &String, hashes: BTreeMap<String, ProjectHashStatus>) {
+        if hashes.is_empty() {
+            self.hashes.remove(project);
+        } else {
+            self.hashes.insert(project.clone(), ProjectHashes(hashes));
+        }
+    }
+
+    /// Returns the hash approved by the most recent poll that is not revoked
+    pub fn latest_approved_hash(&self, project: &String) -> Option<String> {
+        self.hashes(project)
+            .into_iter()
+            .filter_map(|(hash, status)| match status {
+                ProjectHashStatus::Approved { poll_id } => Some((poll_id, hash)),
+                ProjectHashStatus::Revoked { .. } => None,
+            })
+            .max()
+            .map(|(_, hash)| hash)
+    }
+
+    /// Returns the deployment status of the module installed in a canister of the project
+    pub fn deployment_status(
+        &self,
+        project: &String,
+        module_hash: Option<&WasmHash>,
+    ) -> DeploymentStatus {
+        let Some(module_hash) = module_hash else {
+            return DeploymentStatus::Empty;
+        };
+
+        let module_hash = module_hash.to_string();
+        match self.hash_status(project, &module_hash) {
+            Some(ProjectHashStatus::Approved { .. })
+                if self.latest_approved_hash(project).as_ref() == Some(&module_hash) =>
+            {
+                DeploymentStatus::Latest
+            }
+            Some(ProjectHashStatus::Approved { .. }) => DeploymentStatus::Outdated,
+            Some(ProjectHashStatus::Revoked { .. }) => DeploymentStatus::Revoked,
+            None => DeploymentStatus::Unapproved,
+        }
+    }
+
+    /// Returns true if no hash was ever approved
+    pub fn has_no_hashes(&self) -> bool {
+        self.hashes.len() == 0
+    }
+
+    fn check_project_exists(&self, project: &String) -> Result<()> {
+        if self.projects.contains_key(project) {
+            Ok(())
+        } else {
+            Err(UpgraderError::ProjectNotFound(project.clone()))
+        }
+    }
+
+    fn update_hashes(
+        &mut self,
+        project: &String,
+        f: impl FnOnce(&mut BTreeMap<String, ProjectHashStatus>),
+    ) {
+        let mut hashes = self.hashes.get(project).unwrap_or_default();
+        f(&mut hashes.0);
+        self.hashes.insert(project.clone(), hashes);
+    }
+}
+
+/// The status of the hashes of a project by hash
+#[derive(Debug, Default, Deserialize, CandidType, Clone, PartialEq, Eq, Serialize)]
+pub struct ProjectHashes(BTreeMap<String, ProjectHashStatus>);
+
+impl Storable for ProjectHashes {
+    fn to_bytes(&self) -> Cow<[u8]> {
+        codec::encode(self).into()
+    }
+
+    fn from_bytes(bytes: Cow<[u8]>) -> Self {
+        codec::decode(&bytes)
+    }
+
+    const BOUND: Bound = Bound::Unbounded;
 }
 
 #[cfg(test)]
@@ -52,6 +237,7 @@ mod test {
             key: "key".to_string(),
             name: "Project".to_string(),
             description: "Description".to_string(),
+            canisters: None,
         };
 
         // Act
@@ -69,6 +255,7 @@ mod test {
             key: "key".to_string(),
             name: "Project".to_string(),
             description: "Description".to_string(),
+            canisters: None,
         };
 
         // Act
@@ -91,11 +278,13 @@ mod test {
             key: "key1".to_string(),
             name: "Project1".to_string(),
             description: "Description1".to_string(),
+            canisters: None,
         };
         let project2 = ProjectData {
             key: "key2".to_string(),
             name: "Project2".to_string(),
             description: "Description2".to_string(),
+            canisters: None,
         };
 
         // Act
@@ -111,4 +300,170 @@ mod test {
         assert_eq!(all[0], project1);
         assert_eq!(all[1], project2);
     }
+
+    #[test]
+    fn test_project_update() {
+        // Arrange
+        let mut projects = Projects::new(&ic_stable_structures::default_ic_memory_manager());
+        let project = ProjectData {
+            key: "key".to_string(),
+            name: "Project".to_string(),
+            description: "Description".to_string(),
+            canisters: None,
+        };
+        let updated_project = ProjectData {
+            name: "New name".to_string(),
+            ..project.clone()
+        };
+
+        // Act & Assert
+        assert!(projects.update(project.clone()).is_err());
+        assert_eq!(projects.get(&project.key), None);
+
+        projects.insert(project.clone()).unwrap();
+        assert!(projects.update(updated_project.clone()).is_ok());
+        assert_eq!(projects.get(&project.key), Some(updated_project));
+    }
+
+    /// Verifies that an approved hash can be revoked and its status is updated
+    #[test]
+    fn test_project_hash_approve_and_revoke() {
+        // Arrange
+        let mut projects = Projects::new(&ic_stable_structures::default_ic_memory_manager());
+        let project = ProjectData {
+            key: "key".to_string(),
+            name: "Project".to_string(),
+            description: "Description".to_string(),
+            canisters: None,
+        };
+        projects.insert(project.clone()).unwrap();
+
+        // Act & Assert
+        assert!(projects
+            .approve_hash(&"missing".to_string(), "hash", 0)
+            .is_err());
+        assert!(projects
+            .revoke_hash(&project.key, "hash", Some(1), "reason".to_string())
+            .is_err());
+        assert!(projects.has_no_hashes());
+
+        projects.approve_hash(&project.key, "hash", 0).unwrap();
+        assert_eq!(
+            projects.hash_status(&project.key, "hash"),
+            Some(ProjectHashStatus::Approved { poll_id: 0 })
+        );
+        assert!(projects.check_hash_approved(&project.key, "hash").is_ok());
+
+        projects
+            .revoke_hash(&project.key, "hash", Some(1), "vulnerability".to_string())
+            .unwrap();
+        assert_eq!(
+            projects.hash_status(&project.key, "hash"),
+            Some(ProjectHashStatus::Revoked {
+                poll_id: Some(1),
+                reason: "vulnerability".to_string()
+            })
+        );
+        assert!(projects.check_hash_approved(&project.key, "hash").is_err());
+        assert!(projects
+            .revoke_hash(&project.key, "hash", None, "again".to_string())
+            .is_err());
+
+        assert_eq!(projects.hash_status(&project.key, "other"), None);
+        assert_eq!(projects.hashes(&project.key).len(), 1);
+    }
+
+    /// Verifies that a revoked hash can be approved again only explicitly
+    #[test]
+    fn test_project_hash_revoked_is_reapproved_explicitly() {
+        // Arrange
+        let mut projects = Projects::new(&ic_stable_structures::default_ic_memory_manager());
+        let project = ProjectData {
+            key: "key".to_string(),
+            name: "Project".to_string(),
+            description: "Description".to_string(),
+            canisters: None,
+        };
+        projects.insert(project.clone()).unwrap();
+        projects.approve_hash(&project.key, "hash", 0).unwrap();
+
+        // Act & Assert
+        assert!(projects.reapprove_hash(&project.key, "hash", 1).is_err());
+        assert!(projects.reapprove_hash(&project.key, "other", 1).is_err());
+
+        projects
+            .revoke_hash(&project.key, "hash", Some(1), "vulnerability".to_string())
+            .unwrap();
+        assert_eq!(
+            projects.approve_hash(&project.key, "hash", 2),
+            Err(UpgraderError::ProjectHashRevoked {
+                project: project.key.clone(),
+                hash: "hash".to_string()
+            })
+        );
+        assert!(matches!(
+            projects.hash_status(&project.key, "hash"),
+            Some(ProjectHashStatus::Revoked { .. })
+        ));
+
+        projects.reapprove_hash(&project.key, "hash", 3).unwrap();
+        assert_eq!(
+            projects.hash_status(&project.key, "hash"),
+            Some(ProjectHashStatus::Approved { poll_id: 3 })
+        );
+    }
+
+    /// Verifies the deployment status of the installed modules against the approved hashes
+    #[test]
+    fn test_project_deployment_status() {
+        // Arrange
+        let mut projects = Projects::new(&ic_stable_structures::default_ic_memory_manager());
+        let project = ProjectData {
+            key: "key".to_string(),
+            name: "Project".to_string(),
+            description: "Description".to_string(),
+            canisters: None,
+        };
+        projects.insert(project.clone()).unwrap();
+
+        let hash = |byte: u8| WasmHash([byte; 32]);
+        projects
+            .approve_hash(&project.key, &hash(1).to_string(), 1)
+            .unwrap();
+        projects
+            .approve_hash(&project.key, &hash(2).to_string(), 2)
+            .unwrap();
+        projects
+            .approve_hash(&project.key, &hash(3).to_string(), 3)
+            .unwrap();
+        projects
+            .revoke_hash(&project.key, &hash(3).to_string(), None, "bug".to_string())
+            .unwrap();
+
+        // Act & Assert
+        assert_eq!(
+            projects.latest_approved_hash(&project.key),
+            Some(hash(2).to_string())
+        );
+        assert_eq!(
+            projects.deployment_status(&project.key, Some(&hash(2))),
+            DeploymentStatus::Latest
+        );
+        assert_eq!(
+            projects.deployment_status(&project.key, Some(&hash(1))),
+            DeploymentStatus::Outdated
+        );
+        assert_eq!(
+            projects.deployment_status(&project.key, Some(&hash(3))),
+            DeploymentStatus::Revoked
+        );
+        assert_eq!(
+            projects.deployment_status(&project.key, Some(&hash(4))),
+            DeploymentStatus::Unapproved
+        );
+        assert_eq!(
+            projects.deployment_status(&project.key, None),
+            DeploymentStatus::Empty
+        );
+    }
 }
@@ -1,12 +1,16 @@
 use std::borrow::Cow;
+use std::time::Duration;
 
 use candid::{CandidType, Deserialize};
 use ic_stable_structures::stable_structures::Memory;
 use ic_stable_structures::{Bound, CellStructure, MemoryManager, StableCell, Storable};
 use serde::Serialize;
-use upgrader_canister_did::codec;
+use upgrader_canister_did::error::{Result, UpgraderError};
+use upgrader_canister_did::{
+    codec, AdminApprovalSettings, ArchiveSettings, PollSettings, SettingsPatch,
+};
 
-use crate::constant::SETTINGS_MAP_MEMORY_ID;
+use crate::constant::{DEFAULT_NETWORK, POLL_TIMER_INTERVAL, SETTINGS_MAP_MEMORY_ID};
 
 pub struct Settings<M: Memory> {
     settings: StableCell<SettingsData, M>,
@@ -36,6 +40,201 @@ impl<M: Memory> Settings<M> {
         self.read(|s| s.disable_inspect_message)
     }
 
+    /// Sets the multi-admin approval settings.
+    /// Passing `None` disables the multi-admin approval mode.
+    /// The threshold must not be greater than `admins_count`, the number of admins.
+    pub fn set_admin_approval(
+        &mut self,
+        approval: Option<AdminApprovalSettings>,
+        admins_count: u64,
+    ) -> Result<()> {
+        self.check_admin_approval(approval.as_ref(), admins_count)?;
+
+        self.update(|s| {
+            s.admin_approval = approval;
+        });
+        Ok(())
+    }
+
+    /// Returns an error if the multi-admin approval settings are not valid
+    /// or if their threshold is greater than `admins_count`, the number of admins
+    pub fn check_admin_approval(
+        &self,
+        approval: Option<&AdminApprovalSettings>,
+        admins_count: u64,
+    ) -> Result<()> {
+        let Some(approval) = approval else {
+            return Ok(());
+        };
+        if approval.threshold == 0 {
+            return Err(UpgraderError::BadRequest(
+                "The admin approval threshold must be greater than zero".to_string(),
+            ));
+        }
+        if u64::from(approval.threshold) > admins_count {
+            return Err(UpgraderError::BadRequest(format!(
+                "The admin approval threshold {} is greater than the number of admins {admins_count}",
+                approval.threshold
+            )));
+        }
+        if approval.expiry_secs == 0 {
+            return Err(UpgraderError::BadRequest(
+                "The admin action expiry must be greater than zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the threshold of the multi-admin approval is greater than
+    /// `admins_count`, so that the admins cannot be removed below the threshold
+    pub fn check_admins_count(&self, admins_count: u64) -> Result<()> {
+        self.check_admin_approval(self.admin_approval().as_ref(), admins_count)
+    }
+
+    /// Returns the multi-admin approval settings, if the mode is enabled
+    pub fn admin_approval(&self) -> Option<AdminApprovalSettings> {
+        self.read(|s| s.admin_approval)
+    }
+
+    /// Sets the archive settings.
+    /// Passing `None` disables the archiving of the closed polls.
+    pub fn set_archive(&mut self, archive: Option<ArchiveSettings>) {
+        self.update(|s| {
+            s.archive = archive;
+        });
+    }
+
+    /// Returns the archive settings, if the archiving is enabled
+    pub fn archive(&self) -> Option<ArchiveSettings> {
+        self.read(|s| s.archive)
+    }
+
+    /// Sets the interval at which the poll timer finalizes the polls
+    pub fn set_poll_timer_interval(&mut self, interval: Duration) -> Result<()> {
+        if interval.as_secs() == 0 {
+            return Err(UpgraderError::BadRequest(
+                "The poll timer interval must be at least one second".to_string(),
+            ));
+        }
+
+        self.update(|s| {
+            s.poll_timer_interval_secs = Some(interval.as_secs());
+        });
+        Ok(())
+    }
+
+    /// Returns the interval at which the poll timer finalizes the polls.
+    /// Defaults to `POLL_TIMER_INTERVAL` if not set.
+    pub fn poll_timer_interval(&self) -> Duration {
+        self.read(|s| {
+            s.poll_timer_interval_secs
+                .map(Duration::from_secs)
+                .unwrap_or(POLL_TIMER_INTERVAL)
+        })
+    }
+
+    /// Sets the network of the upgrader canister
+    pub fn set_network(&mut self, network: Option<String>) {
+        self.update(|s| {
+            s.network = network;
+        });
+    }
+
+    /// Returns the network of the upgrader canister.
+    /// Defaults to `DEFAULT_NETWORK` if not set.
+    pub fn network(&self) -> String {
+        self.read(|s| {
+            s.network
+                .clone()
+                .unwrap_or_else(|| DEFAULT_NETWORK.to_string())
+        })
+    }
+
+    /// Returns the settings applied to the polls
+    pub fn poll_settings(&self) -> PollSettings {
+        self.read(|s| PollSettings {
+            quorum: s.poll_quorum.unwrap_or_default(),
+            min_duration_secs: s.poll_min_duration_secs,
+            max_duration_secs: s.poll_max_duration_secs,
+        })
+    }
+
+    /// Returns an error if the duration of a new poll is out of the configured bounds
+    pub fn check_poll_duration(
+        &self,
+        start_timestamp_secs: u64,
+        end_timestamp_secs: u64,
+    ) -> Result<()> {
+        let duration_secs = end_timestamp_secs.saturating_sub(start_timestamp_secs);
+        let poll_settings = self.poll_settings();
+
+        if let Some(min_duration_secs) = poll_settings.min_duration_secs {
+            if duration_secs < min_duration_secs {
+                return Err(UpgraderError::BadRequest(format!(
+                    "The poll duration must be at least {min_duration_secs} seconds"
+                )));
+            }
+        }
+        if let Some(max_duration_secs) = poll_settings.max_duration_secs {
+            if duration_secs > max_duration_secs {
+                return Err(UpgraderError::BadRequest(format!(
+                    "The poll duration must be at most {max_duration_secs} seconds"
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns an error if the patch cannot be applied to the current settings
+    pub fn check_patch(&self, patch: &SettingsPatch) -> Result<()> {
+        if patch.poll_timer_interval_secs == Some(0) {
+            return Err(UpgraderError::BadRequest(
+                "The poll timer interval must be at least one second".to_string(),
+            ));
+        }
+
+        let min_duration_secs = patch
+            .poll_min_duration_secs
+            .unwrap_or(self.read(|s| s.poll_min_duration_secs));
+        let max_duration_secs = patch
+            .poll_max_duration_secs
+            .unwrap_or(self.read(|s| s.poll_max_duration_secs));
+        if let (Some(min), Some(max)) = (min_duration_secs, max_duration_secs) {
+            if min > max {
+                return Err(UpgraderError::BadRequest(
+                    "The minimum poll duration must not be greater than the maximum poll duration"
+                        .to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies a partial update of the settings.
+    /// The patch is checked before applying it, so either all or none of the fields are updated.
+    pub fn apply_patch(&mut self, patch: &SettingsPatch) -> Result<()> {
+        self.check_patch(patch)?;
+
+        self.update(|s| {
+            if let Some(disable_inspect_message) = patch.disable_inspect_message {
+                s.disable_inspect_message = disable_inspect_message;
+            }
+            if let Some(poll_quorum) = patch.poll_quorum {
+                s.poll_quorum = Some(poll_quorum);
+            }
+            if let Some(poll_timer_interval_secs) = patch.poll_timer_interval_secs {
+                s.poll_timer_interval_secs = Some(poll_timer_interval_secs);
+            }
+            if let Some(poll_min_duration_secs) = patch.poll_min_duration_secs {
+                s.poll_min_duration_secs = poll_min_duration_secs;
+            }
+            if let Some(poll_max_duration_secs) = patch.poll_max_duration_secs {
+                s.poll_max_duration_secs = poll_max_duration_secs;
+            }
+        });
+        Ok(())
+    }
+
     fn read<F, T>(&self, f: F) -> T
     where
         for<'a> F: FnOnce(&'a SettingsData) -> T,
@@ -58,6 +257,13 @@ impl<M: Memory> Settings<M> {
 #[derive(Debug, Default, Deserialize, CandidType, Clone, PartialEq, Eq, Serialize)]
 pub struct SettingsData {
     disable_inspect_message: bool,
+    admin_approval: Option<AdminApprovalSettings>,
+    archive: Option<ArchiveSettings>,
+    poll_timer_interval_secs: Option<u64>,
+    poll_quorum: Option<u64>,
+    poll_min_duration_secs: Option<u64>,
+    poll_max_duration_secs: Option<u64>,
+    network: Option<String>,
 }
 
 impl Storable for SettingsData {
@@ -101,4 +307,233 @@ mod tests {
         settings.disable_inspect_message(true);
         assert!(settings.is_inspect_message_disabled());
     }
+
+    /// Test the multi-admin approval is disabled by default and can be set
+    #[test]
+    fn test_set_admin_approval() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        assert_eq!(settings.admin_approval(), None);
+
+        let approval = AdminApprovalSettings {
+            threshold: 2,
+            expiry_secs: 3600,
+        };
+        settings.set_admin_approval(Some(approval), 2).unwrap();
+        assert_eq!(settings.admin_approval(), Some(approval));
+
+        settings.set_admin_approval(None, 2).unwrap();
+        assert_eq!(settings.admin_approval(), None);
+    }
+
+    /// Test the poll timer interval defaults to POLL_TIMER_INTERVAL and can be set
+    #[test]
+    fn test_set_poll_timer_interval() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        assert_eq!(settings.poll_timer_interval(), POLL_TIMER_INTERVAL);
+
+        settings
+            .set_poll_timer_interval(Duration::from_secs(30))
+            .unwrap();
+        assert_eq!(settings.poll_timer_interval(), Duration::from_secs(30));
+
+        assert!(settings
+            .set_poll_timer_interval(Duration::from_millis(500))
+            .is_err());
+        assert_eq!(settings.poll_timer_interval(), Duration::from_secs(30));
+    }
+
+    /// Test the archive is disabled by default and can be set
+    #[test]
+    fn test_set_archive() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        assert_eq!(settings.archive(), None);
+
+        let archive = ArchiveSettings {
+            canister_id: candid::Principal::from_slice(&[1; 29]),
+            retention_secs: 3600,
+        };
+        settings.set_archive(Some(archive));
+        assert_eq!(settings.archive(), Some(archive));
+
+        settings.set_archive(None);
+        assert_eq!(settings.archive(), None);
+    }
+
+    /// Test that invalid multi-admin approval settings are rejected
+    #[test]
+    fn test_set_invalid_admin_approval() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+
+        assert!(settings
+            .set_admin_approval(
+                Some(AdminApprovalSettings {
+                    threshold: 0,
+                    expiry_secs: 3600,
+                }),
+                2
+            )
+            .is_err());
+        assert!(settings
+            .set_admin_approval(
+                Some(AdminApprovalSettings {
+                    threshold: 3,
+                    expiry_secs: 3600,
+                }),
+                2
+            )
+            .is_err());
+        assert!(settings
+            .set_admin_approval(
+                Some(AdminApprovalSettings {
+                    threshold: 2,
+                    expiry_secs: 0,
+                }),
+                2
+            )
+            .is_err());
+        assert_eq!(settings.admin_approval(), None);
+    }
+
+    /// Test that the admins cannot be fewer than the multi-admin approval threshold
+    #[test]
+    fn test_check_admins_count() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        assert!(settings.check_admins_count(0).is_ok());
+
+        settings
+            .set_admin_approval(
+                Some(AdminApprovalSettings {
+                    threshold: 2,
+                    expiry_secs: 3600,
+                }),
+                3,
+            )
+            .unwrap();
+        assert!(settings.check_admins_count(2).is_ok());
+        assert!(settings.check_admins_count(1).is_err());
+    }
+
+    /// Test that a settings patch updates only the fields that are set
+    #[test]
+    fn test_apply_patch() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        assert_eq!(
+            settings.poll_settings(),
+            PollSettings {
+                quorum: 0,
+                min_duration_secs: None,
+                max_duration_secs: None,
+            }
+        );
+
+        settings
+            .apply_patch(&SettingsPatch {
+                disable_inspect_message: Some(true),
+                poll_quorum: Some(3),
+                poll_timer_interval_secs: Some(60),
+                poll_min_duration_secs: Some(Some(10)),
+                poll_max_duration_secs: None,
+            })
+            .unwrap();
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_max_duration_secs: Some(Some(100)),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(settings.is_inspect_message_disabled());
+        assert_eq!(settings.poll_timer_interval(), Duration::from_secs(60));
+        assert_eq!(
+            settings.poll_settings(),
+            PollSettings {
+                quorum: 3,
+                min_duration_secs: Some(10),
+                max_duration_secs: Some(100),
+            }
+        );
+    }
+
+    /// Test that an invalid settings patch is rejected without updating any field
+    #[test]
+    fn test_apply_invalid_patch() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_max_duration_secs: Some(Some(100)),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(settings
+            .apply_patch(&SettingsPatch {
+                disable_inspect_message: Some(true),
+                poll_min_duration_secs: Some(Some(200)),
+                ..Default::default()
+            })
+            .is_err());
+        assert!(settings
+            .apply_patch(&SettingsPatch {
+                poll_timer_interval_secs: Some(0),
+                ..Default::default()
+            })
+            .is_err());
+
+        assert!(!settings.is_inspect_message_disabled());
+        assert_eq!(settings.poll_timer_interval(), POLL_TIMER_INTERVAL);
+        assert_eq!(settings.poll_settings().min_duration_secs, None);
+    }
+
+    /// Test that the poll duration is checked against the configured bounds
+    #[test]
+    fn test_check_poll_duration() {
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        assert!(settings.check_poll_duration(0, u64::MAX).is_ok());
+
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_min_duration_secs: Some(Some(10)),
+                poll_max_duration_secs: Some(Some(100)),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(settings.check_poll_duration(100, 109).is_err());
+        assert!(settings.check_poll_duration(100, 110).is_ok());
+        assert!(settings.check_poll_duration(100, 200).is_ok());
+        assert!(settings.check_poll_duration(100, 201).is_err());
+    }
+
+    /// Test that a poll duration bound set to `Some(None)` is cleared
+    #[test]
+    fn test_apply_patch_clears_duration_bounds() {
+        // Arrange
+        let mut settings = Settings::new(&ic_stable_structures::default_ic_memory_manager());
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_min_duration_secs: Some(Some(10)),
+                poll_max_duration_secs: Some(Some(100)),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Act
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_max_duration_secs: Some(None),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Assert
+        assert_eq!(settings.poll_settings().min_duration_secs, Some(10));
+        assert_eq!(settings.poll_settings().max_duration_secs, None);
+        assert!(settings.check_poll_duration(100, u64::MAX).is_ok());
+        assert!(settings
+            .apply_patch(&SettingsPatch {
+                poll_min_duration_secs: Some(Some(u64::MAX)),
+                ..Default::default()
+            })
+            .is_ok());
+    }
 }
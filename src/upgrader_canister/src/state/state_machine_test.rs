@@ -0,0 +1,442 @@
+//! Model based tests of `Polls` and `Permissions`.
+//! Random sequences of operations are applied both to the stable state and to a simple
+//! in-memory model, and the state invariants are checked after every operation.
+
+use std::collections::{HashMap, HashSet};
+
+use candid::Principal;
+use ic_stable_structures::stable_structures::DefaultMemoryImpl;
+use ic_stable_structures::{default_ic_memory_manager, VirtualMemory};
+use proptest::prelude::*;
+use upgrader_canister_did::{
+    Permission, PollCreateData, PollResult, PollType, SettingsPatch, UpgraderError,
+};
+
+use super::permission::Permissions;
+use super::polls::Polls;
+use super::projects::Projects;
+use super::settings::Settings;
+
+type TestMemory = VirtualMemory<DefaultMemoryImpl>;
+
+/// The number of principals used by the operations
+const PRINCIPALS: u8 = 4;
+
+/// The permissions used by the operations
+const PERMISSIONS: [Permission; 4] = [
+    Permission::Admin,
+    Permission::CreateProject,
+    Permission::CreatePoll,
+    Permission::VotePoll,
+];
+
+#[derive(Debug, Clone)]
+enum Operation {
+    CreatePoll {
+        add: bool,
+        principal: u8,
+        permission: usize,
+        start_delay_secs: u64,
+        duration_secs: u64,
+    },
+    Vote {
+        poll: u64,
+        voter: u8,
+        approved: bool,
+    },
+    AdvanceTime(u64),
+    FinalizeDue,
+    Finalize {
+        poll: u64,
+    },
+    AddPermission {
+        principal: u8,
+        permission: usize,
+    },
+    RemovePermission {
+        principal: u8,
+        permission: usize,
+    },
+}
+
+fn operation() -> impl Strategy<Value = Operation> {
+    prop_oneof![
+        2 => (
+            any::<bool>(),
+            0..PRINCIPALS,
+            0..PERMISSIONS.len(),
+            0..20u64,
+            0..50u64
+        )
+            .prop_map(
+                |(add, principal, permission, start_delay_secs, duration_secs)| {
+                    Operation::CreatePoll {
+                        add,
+                        principal,
+                        permission,
+                        start_delay_secs,
+                        duration_secs,
+                    }
+                }
+            ),
+        4 => (0..16u64, 0..PRINCIPALS, any::<bool>()).prop_map(|(poll, voter, approved)| {
+            Operation::Vote {
+                poll,
+                voter,
+                approved,
+            }
+        }),
+        2 => (0..30u64).prop_map(Operation::AdvanceTime),
+        1 => Just(Operation::FinalizeDue),
+        1 => (0..16u64).prop_map(|poll| Operation::Finalize { poll }),
+        1 => (0..PRINCIPALS, 0..PERMISSIONS.len()).prop_map(|(principal, permission)| {
+            Operation::AddPermission {
+                principal,
+                permission,
+            }
+        }),
+        1 => (0..PRINCIPALS, 0..PERMISSIONS.len()).prop_map(|(principal, permission)| {
+            Operation::RemovePermission {
+                principal,
+                permission,
+            }
+        }),
+    ]
+}
+
+fn principal(index: u8) -> Principal {
+    Principal::from_slice(&[index + 1; 29])
+}
+
+/// Returns the result of a poll with the given votes, as computed by the canister
+fn tally_result(yes_votes: usize, no_votes: usize, quorum: u64) -> PollResult {
+    if (yes_votes + no_votes) as u64 >= quorum && yes_votes > no_votes {
+        PollResult::Accepted
+    } else {
+        PollResult::Rejected
+    }
+}
+
+/// A poll of the model
+struct ModelPoll {
+    add: bool,
+    principal: Principal,
+    permission: Permission,
+    start_timestamp_secs: u64,
+    end_timestamp_secs: u64,
+    votes: HashMap<Principal, bool>,
+    result: Option<PollResult>,
+}
+
+/// The expected state, the poll ids are the indexes of the polls
+struct Model {
+    now: u64,
+    quorum: u64,
+    polls: Vec<ModelPoll>,
+    permissions: HashMap<Principal, HashSet<Permission>>,
+}
+
+impl Model {
+    fn add_permission(&mut self, principal: Principal, permission: Permission) {
+        self.permissions
+            .entry(principal)
+            .or_default()
+            .insert(permission);
+    }
+
+    fn remove_permission(&mut self, principal: Principal, permission: &Permission) {
+        if let Some(permissions) = self.permissions.get_mut(&principal) {
+            permissions.remove(permission);
+        }
+    }
+
+    /// Closes the poll and applies its result
+    fn close(&mut self, id: u64) -> PollResult {
+        let poll = &self.polls[id as usize];
+        let yes_votes = poll.votes.values().filter(|approved| **approved).count();
+        let result = tally_result(yes_votes, poll.votes.len() - yes_votes, self.quorum);
+
+        if result == PollResult::Accepted {
+            let (add, principal, permission) = (poll.add, poll.principal, poll.permission.clone());
+            if add {
+                self.add_permission(principal, permission);
+            } else {
+                self.remove_permission(principal, &permission);
+            }
+        }
+        self.polls[id as usize].result = Some(result.clone());
+        result
+    }
+
+    /// Returns an id among the created polls or the next one, which does not exist
+    fn poll_id(&self, poll: u64) -> u64 {
+        poll % (self.polls.len() as u64 + 1)
+    }
+
+    fn is_pending(&self, id: u64) -> bool {
+        self.polls
+            .get(id as usize)
+            .is_some_and(|poll| poll.result.is_none())
+    }
+}
+
+/// The state under test and its model
+struct StateMachine {
+    polls: Polls<TestMemory>,
+    permissions: Permissions<TestMemory>,
+    settings: Settings<TestMemory>,
+    projects: Projects<TestMemory>,
+    model: Model,
+}
+
+impl StateMachine {
+    fn new(quorum: u64) -> Self {
+        let memory_manager = default_ic_memory_manager();
+        let mut settings = Settings::new(&memory_manager);
+        settings
+            .apply_patch(&SettingsPatch {
+                poll_quorum: Some(quorum),
+                ..Default::default()
+            })
+            .unwrap();
+
+        Self {
+            polls: Polls::new(&memory_manager),
+            permissions: Permissions::new(&memory_manager),
+            settings,
+            projects: Projects::new(&memory_manager),
+            model: Model {
+                now: 0,
+                quorum,
+                polls: vec![],
+                permissions: HashMap::new(),
+            },
+        }
+    }
+
+    fn apply(&mut self, operation: Operation) {
+        let polls = &mut self.polls;
+        match operation {
+            Operation::CreatePoll {
+                add,
+                principal: principal_index,
+                permission,
+                start_delay_secs,
+                duration_secs,
+            } => {
+                let principal = principal(principal_index);
+                let permission = PERMISSIONS[permission].clone();
+                let principals = vec![principal];
+                let permissions = vec![permission.clone()];
+                let start_timestamp_secs = self.model.now + start_delay_secs;
+                let end_timestamp_secs = start_timestamp_secs + duration_secs;
+
+                let id = polls.insert(PollCreateData {
+                    description: "poll".to_string(),
+                    poll_type: if add {
+                        PollType::AddPermission {
+                            principals,
+                            permissions,
+                        }
+                    } else {
+                        PollType::RemovePermission {
+                            principals,
+                            permissions,
+                        }
+                    },
+                    start_timestamp_secs,
+                    end_timestamp_secs,
+                });
+
+                // the ids are unique and monotonic
+                assert_eq!(id, self.model.polls.len() as u64);
+                self.model.polls.push(ModelPoll {
+                    add,
+                    principal,
+                    permission,
+                    start_timestamp_secs,
+                    end_timestamp_secs,
+                    votes: HashMap::new(),
+                    result: None,
+                });
+            }
+            Operation::Vote {
+                poll,
+                voter,
+                approved,
+            } => {
+                let id = self.model.poll_id(poll);
+                let voter = principal(voter);
+                let now = self.model.now;
+
+                let result = polls.vote(id, voter, approved, now);
+
+                let expected = match self.model.polls.get_mut(id as usize) {
+                    None => Err(UpgraderError::PollNotFound(id)),
+                    Some(poll) if poll.result.is_some() || now > poll.end_timestamp_secs => {
+                        Err(UpgraderError::PollClosed {
+                            ended_at: poll.end_timestamp_secs,
+                        })
+                    }
+                    Some(poll) if now < poll.start_timestamp_secs => {
+                        Err(UpgraderError::PollNotOpen {
+                            starts_at: poll.start_timestamp_secs,
+                        })
+                    }
+                    Some(poll) => {
+                        poll.votes.insert(voter, approved);
+                        Ok(())
+                    }
+                };
+                assert_eq!(result, expected);
+            }
+            Operation::AdvanceTime(secs) => {
+                self.model.now += secs;
+            }
+            Operation::FinalizeDue => {
+                let now = self.model.now;
+
+                let closed = polls.finalize_polls(
+                    now,
+                    &mut self.permissions,
+                    &mut self.settings,
+                    &mut self.projects,
+                    || true,
+                );
+
+                let due_ids: Vec<u64> = (0..self.model.polls.len() as u64)
+                    .filter(|id| {
+                        self.model.is_pending(*id)
+                            && now > self.model.polls[*id as usize].end_timestamp_secs
+                    })
+                    .collect();
+                assert_eq!(closed.keys().copied().collect::<Vec<_>>(), due_ids);
+                for id in due_ids {
+                    assert_eq!(closed[&id].result, self.model.close(id));
+                }
+            }
+            Operation::Finalize { poll } => {
+                let id = self.model.poll_id(poll);
+                let now = self.model.now;
+
+                let result = polls.finalize_poll(
+                    id,
+                    now,
+                    &mut self.permissions,
+                    &mut self.settings,
+                    &mut self.projects,
+                );
+
+                if !self.model.is_pending(id) {
+                    assert_eq!(result, Err(UpgraderError::PollNotFound(id)));
+                } else if now <= self.model.polls[id as usize].end_timestamp_secs {
+                    assert!(matches!(result, Err(UpgraderError::BadRequest(_))));
+                } else {
+                    assert_eq!(result.unwrap().result, self.model.close(id));
+                }
+            }
+            Operation::AddPermission {
+                principal: principal_index,
+                permission,
+            } => {
+                let principal = principal(principal_index);
+                let permission = PERMISSIONS[permission].clone();
+                self.permissions
+                    .add_permissions(principal, vec![permission.clone()])
+                    .unwrap();
+                self.model.add_permission(principal, permission);
+            }
+            Operation::RemovePermission {
+                principal: principal_index,
+                permission,
+            } => {
+                let principal = principal(principal_index);
+                let permission = PERMISSIONS[permission].clone();
+                self.permissions
+                    .remove_permissions(principal, &[permission.clone()])
+                    .unwrap();
+                self.model.remove_permission(principal, &permission);
+            }
+        }
+    }
+
+    fn check_invariants(&self) {
+        let pending = self.polls.all_pending();
+        let closed = self.polls.all_closed();
+        assert_eq!(pending.len() + closed.len(), self.model.polls.len());
+
+        for (id, model_poll) in self.model.polls.iter().enumerate() {
+            let id = id as u64;
+
+            // a poll lives in exactly one of pending and closed
+            assert_ne!(pending.contains_key(&id), closed.contains_key(&id));
+            assert_eq!(pending.contains_key(&id), model_poll.result.is_none());
+
+            let (yes_voters, no_voters) = match &model_poll.result {
+                None => (&pending[&id].yes_voters, &pending[&id].no_voters),
+                Some(result) => {
+                    let poll = &closed[&id];
+                    // the result matches the tallies
+                    assert_eq!(&poll.result, result);
+                    assert_eq!(
+                        poll.result,
+                        tally_result(
+                            poll.yes_voters.len(),
+                            poll.no_voters.len(),
+                            self.model.quorum
+                        )
+                    );
+                    (&poll.yes_voters, &poll.no_voters)
+                }
+            };
+
+            // the voters are never both yes and no, nor counted twice
+            let yes_set: HashSet<Principal> = yes_voters.iter().copied().collect();
+            let no_set: HashSet<Principal> = no_voters.iter().copied().collect();
+            assert_eq!(yes_set.len(), yes_voters.len());
+            assert_eq!(no_set.len(), no_voters.len());
+            assert!(yes_set.is_disjoint(&no_set));
+
+            let expected_yes: HashSet<Principal> = model_poll
+                .votes
+                .iter()
+                .filter(|(_, approved)| **approved)
+                .map(|(voter, _)| *voter)
+                .collect();
+            assert_eq!(yes_set, expected_yes);
+            assert_eq!(yes_set.len() + no_set.len(), model_poll.votes.len());
+        }
+
+        for index in 0..PRINCIPALS {
+            let principal = principal(index);
+            assert_eq!(
+                self.permissions.get_permissions(&principal).permissions,
+                self.model
+                    .permissions
+                    .get(&principal)
+                    .cloned()
+                    .unwrap_or_default()
+            );
+        }
+    }
+}
+
+proptest! {
+    /// Should keep the polls and permissions invariants for any sequence of operations
+    #[test]
+    fn test_polls_and_permissions_invariants(
+        quorum in 0..4u64,
+        operations in proptest::collection::vec(operation(), 1..80),
+    ) {
+        // Arrange
+        let mut state_machine = StateMachine::new(quorum);
+
+        for operation in operations {
+            // Act
+            state_machine.apply(operation);
+
+            // Assert
+            state_machine.check_invariants();
+        }
+    }
+}
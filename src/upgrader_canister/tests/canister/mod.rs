@@ -1,12 +1,25 @@
 use std::sync::Arc;
+use std::time::{Duration, UNIX_EPOCH};
 
 use candid::Principal;
 use ic_canister_client::CanisterClientResult;
 use ic_exports::pocket_ic::PocketIc;
-use upgrader_canister::constant::POLL_TIMER_INTERVAL;
-use upgrader_canister_did::{Permission, Poll, PollCreateData, PollResult, PollType, ProjectData};
-
-use crate::pocket_ic::{build_client, deploy_canister, ADMIN};
+use upgrader_canister::constant::{
+    ALERTS_TIMER_INTERVAL, ARCHIVE_TIMER_INTERVAL, POLL_TIMER_INTERVAL,
+};
+use upgrader_canister_client::WaitPollError;
+use upgrader_canister_did::{
+    AdminAction, AdminApprovalSettings, ArchiveSettings, AuditLogOutcome, DeploymentStatus,
+    Permission, Poll, PollAction, PollCreateData, PollResult, PollSettings, PollType,
+    ProjectCanister, ProjectData, ProjectHashStatus, SettingsPatch, UpgraderError,
+};
+
+use crate::pocket_ic::{
+    build_archive_client, build_client, deploy_archive_canister, deploy_canister, ADMIN,
+};
+
+/// A valid wasm hash used in the project hash polls
+const WASM_HASH: &str = "b1a2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90";
 
 /// Test that the canister_build_data query returns the correct data
 #[tokio::test]
@@ -199,6 +212,159 @@ async fn test_only_admin_can_disable_inspect_message() {
     }
 }
 
+/// Test that admin actions require the approval of multiple admins if enabled
+#[tokio::test]
+async fn test_admin_actions_require_multiple_admins_approval() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    let admin_2_principal = Principal::from_slice(&[2u8; 29]);
+    let admin_2_client = build_client(pocket.clone(), canister_principal, admin_2_principal);
+    let user_principal = Principal::from_slice(&[1u8; 29]);
+
+    admin_client
+        .admin_permissions_add(admin_2_principal, &[Permission::Admin])
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .admin_approval_settings_set(Some(AdminApprovalSettings {
+            threshold: 2,
+            expiry_secs: 3600,
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    let result = admin_client
+        .admin_permissions_add(user_principal, &[Permission::CreatePoll])
+        .await
+        .unwrap();
+
+    // Assert
+    let action_id = match result {
+        Err(UpgraderError::AdminApprovalRequired(id)) => id,
+        other => panic!("Expected AdminApprovalRequired, got {other:?}"),
+    };
+    assert!(admin_client
+        .admin_permissions_get(user_principal)
+        .await
+        .unwrap()
+        .unwrap()
+        .permissions
+        .is_empty());
+
+    let pending = admin_2_client
+        .admin_action_get_all_pending()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert_eq!(
+        pending[&action_id].action,
+        AdminAction::PermissionsAdd {
+            principal: user_principal,
+            permissions: vec![Permission::CreatePoll],
+        }
+    );
+    assert_eq!(pending[&action_id].approvals, vec![ADMIN]);
+
+    // The same admin cannot approve twice
+    assert!(!admin_client
+        .admin_action_approve(action_id)
+        .await
+        .unwrap()
+        .unwrap());
+    assert!(admin_2_client
+        .admin_action_approve(action_id)
+        .await
+        .unwrap()
+        .unwrap());
+
+    assert!(admin_client
+        .admin_permissions_get(user_principal)
+        .await
+        .unwrap()
+        .unwrap()
+        .permissions
+        .contains(&Permission::CreatePoll));
+    assert!(admin_client
+        .admin_action_get_all_pending()
+        .await
+        .unwrap()
+        .unwrap()
+        .is_empty());
+}
+
+/// Test that the state-changing calls of the principals with permissions are recorded
+/// in the audit log, readable only by the admins
+#[tokio::test]
+async fn test_state_changing_calls_are_recorded_in_audit_log() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    let user_principal = Principal::from_slice(&[1u8; 29]);
+    let user_client = build_client(pocket.clone(), canister_principal, user_principal);
+    let stranger_client = build_client(
+        pocket.clone(),
+        canister_principal,
+        Principal::from_slice(&[2u8; 29]),
+    );
+    disable_inspect_message(pocket, canister_principal).await;
+
+    // Act
+    admin_client
+        .admin_permissions_add(user_principal, &[Permission::VotePoll])
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(user_client
+        .admin_permissions_add(user_principal, &[Permission::Admin])
+        .await
+        .unwrap()
+        .is_err());
+    assert!(stranger_client
+        .admin_permissions_add(user_principal, &[Permission::Admin])
+        .await
+        .unwrap()
+        .is_err());
+
+    // Assert
+    assert_eq!(admin_client.audit_log_len().await.unwrap().unwrap(), 3);
+    let entries = admin_client.audit_log_get(0, 10).await.unwrap().unwrap();
+    assert_eq!(entries.len(), 3);
+
+    assert_eq!(entries[&0].method, "admin_disable_inspect_message");
+    assert_eq!(entries[&0].caller, ADMIN);
+    assert_eq!(entries[&0].outcome, AuditLogOutcome::Success);
+
+    assert_eq!(entries[&1].method, "admin_permissions_add");
+    assert_eq!(entries[&1].caller, ADMIN);
+    assert!(entries[&1].args.contains(&user_principal.to_text()));
+    assert_eq!(entries[&1].outcome, AuditLogOutcome::Success);
+
+    assert_eq!(entries[&2].method, "admin_permissions_add");
+    assert_eq!(entries[&2].caller, user_principal);
+    assert_eq!(
+        entries[&2].outcome,
+        AuditLogOutcome::Failure(UpgraderError::MissingPermissions(vec![Permission::Admin]))
+    );
+
+    let page = admin_client.audit_log_get(1, 1).await.unwrap().unwrap();
+    assert_eq!(page.len(), 1);
+    assert_eq!(page[&1], entries[&1]);
+
+    assert_eq!(
+        user_client.audit_log_get(0, 10).await.unwrap(),
+        Err(UpgraderError::MissingPermissions(vec![Permission::Admin]))
+    );
+    assert_eq!(
+        user_client.audit_log_len().await.unwrap(),
+        Err(UpgraderError::MissingPermissions(vec![Permission::Admin]))
+    );
+}
+
 /// Test that the caller can get their own permissions
 #[tokio::test]
 async fn test_caller_can_get_own_permissions() {
@@ -253,6 +419,7 @@ async fn test_caller_can_create_and_get_projects() {
         key: "key".to_string(),
         name: "Project".to_string(),
         description: "Description".to_string(),
+        canisters: None,
     };
     user_1_client
         .project_create(&project)
@@ -286,6 +453,7 @@ async fn test_caller_cant_create_projects_if_not_allowed() {
         key: "key".to_string(),
         name: "Project".to_string(),
         description: "Description".to_string(),
+        canisters: None,
     };
     assert_inspect_message_error(&user_1_client.project_create(&project).await);
 
@@ -334,7 +502,7 @@ async fn test_caller_can_create_and_get_polls() {
         description: "Description".to_string(),
         poll_type: PollType::ProjectHash {
             project: project_key.to_string(),
-            hash: "hash".to_string(),
+            hash: WASM_HASH.to_string(),
         },
         start_timestamp_secs: 0,
         end_timestamp_secs: 1,
@@ -378,7 +546,7 @@ async fn test_caller_cant_create_poll_for_not_existing_project() {
         description: "Description".to_string(),
         poll_type: PollType::ProjectHash {
             project: "project".to_string(),
-            hash: "hash".to_string(),
+            hash: WASM_HASH.to_string(),
         },
         start_timestamp_secs: 0,
         end_timestamp_secs: 1,
@@ -388,207 +556,766 @@ async fn test_caller_cant_create_poll_for_not_existing_project() {
     assert!(user_1_client.poll_create(&poll).await.unwrap().is_err());
 }
 
-/// Test that the caller can't create polls if not allowed
-#[tokio::test]
-async fn test_caller_cant_create_polls_if_not_allowed() {
-    // Arrange
-    let (pocket, canister_principal) = deploy_canister(None).await;
-    let user_1_principal = Principal::from_slice(&[1u8; 29]);
-    let user_1_client = build_client(pocket.clone(), canister_principal, user_1_principal);
-
-    let project_key = "project-1";
-    create_project(pocket.clone(), canister_principal, project_key).await;
-
-    // Act
-    let poll = PollCreateData {
-        description: "Description".to_string(),
-        poll_type: PollType::ProjectHash {
-            project: project_key.to_string(),
-            hash: "hash".to_string(),
-        },
-        start_timestamp_secs: 0,
-        end_timestamp_secs: 1,
-    };
-    assert_inspect_message_error(&user_1_client.poll_create(&poll).await);
-
-    // Permission check should fail even if the inspect message is disabled
-    {
-        disable_inspect_message(pocket, canister_principal).await;
-
-        // Act
-        assert!(user_1_client.poll_create(&poll).await.unwrap().is_err());
-    }
-
-    // Assert
-    let polls = user_1_client.poll_get_all_pending().await.unwrap();
-    assert!(polls.is_empty());
-}
-
-/// Test that the caller can vote in a poll
+/// Test that the actions of a batch poll are validated at creation and applied on acceptance
 #[tokio::test]
-async fn test_caller_can_vote_in_poll() {
+async fn test_batch_poll_is_validated_and_applied() {
     // Arrange
     let (pocket, canister_principal) = deploy_canister(None).await;
-    let user_1_principal = Principal::from_slice(&[1u8; 29]);
-    let user_1_client = build_client(pocket.clone(), canister_principal, user_1_principal);
-    let user_2_principal = Principal::from_slice(&[2u8; 29]);
-    let user_2_client = build_client(pocket.clone(), canister_principal, user_2_principal);
     let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
 
-    let project_key = "project-10";
+    let project_key = "project-12";
     create_project(pocket.clone(), canister_principal, project_key).await;
 
     admin_client
-        .admin_permissions_add(
-            user_1_principal,
-            &[Permission::CreatePoll, Permission::VotePoll],
-        )
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll, Permission::VotePoll])
         .await
         .unwrap()
         .unwrap();
-    admin_client
-        .admin_permissions_add(user_2_principal, &[Permission::VotePoll])
+
+    let voter = Principal::from_slice(&[1u8; 29]);
+    let now_secs = pocket
+        .get_time()
         .await
+        .duration_since(UNIX_EPOCH)
         .unwrap()
-        .unwrap();
-
-    let poll = PollCreateData {
-        description: "Description".to_string(),
-        poll_type: PollType::ProjectHash {
-            project: project_key.to_string(),
-            hash: "hash".to_string(),
-        },
+        .as_secs();
+    let batch_poll = |actions: Vec<PollAction>| PollCreateData {
+        description: "Release".to_string(),
+        poll_type: PollType::Batch(actions),
         start_timestamp_secs: 0,
-        end_timestamp_secs: u64::MAX,
+        end_timestamp_secs: now_secs + 100,
     };
-    let poll_id = user_1_client.poll_create(&poll).await.unwrap().unwrap();
 
     // Act
-    user_1_client
-        .poll_vote(poll_id, false)
+    let empty_batch_result = admin_client.poll_create(&batch_poll(vec![])).await.unwrap();
+    let missing_project_result = admin_client
+        .poll_create(&batch_poll(vec![PollAction::ProjectHash {
+            project: "missing".to_string(),
+            hash: WASM_HASH.to_string(),
+        }]))
+        .await
+        .unwrap();
+    let anonymous_result = admin_client
+        .poll_create(&batch_poll(vec![PollAction::AddPermission {
+            principals: vec![Principal::anonymous()],
+            permissions: vec![Permission::VotePoll],
+        }]))
+        .await
+        .unwrap();
+
+    let poll_id = admin_client
+        .poll_create(&batch_poll(vec![
+            PollAction::AddPermission {
+                principals: vec![voter],
+                permissions: vec![Permission::VotePoll],
+            },
+            PollAction::ProjectHash {
+                project: project_key.to_string(),
+                hash: WASM_HASH.to_string(),
+            },
+        ]))
         .await
         .unwrap()
         .unwrap();
-    user_2_client
+    admin_client
         .poll_vote(poll_id, true)
         .await
         .unwrap()
         .unwrap();
 
+    pocket.advance_time(Duration::from_secs(101)).await;
+    admin_client.polls_finalize_due().await.unwrap().unwrap();
+
     // Assert
-    let poll = user_1_client.poll_get(poll_id).await.unwrap().unwrap();
+    assert!(empty_batch_result.is_err());
+    assert_eq!(
+        missing_project_result,
+        Err(UpgraderError::ProjectNotFound("missing".to_string()))
+    );
+    assert_eq!(
+        anonymous_result,
+        Err(UpgraderError::AnonymousPrincipalNotAllowed)
+    );
 
-    match poll {
-        Poll::Pending(poll) => {
-            assert_eq!(poll.yes_voters.len(), 1);
-            assert!(poll.yes_voters.contains(&user_2_principal));
-            assert_eq!(poll.no_voters.len(), 1);
-            assert!(poll.no_voters.contains(&user_1_principal));
-        }
-        _ => panic!("Poll should be pending"),
-    }
+    let closed_poll = admin_client
+        .poll_get_closed(poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(closed_poll.result, PollResult::Accepted);
+    assert!(admin_client
+        .admin_permissions_get(voter)
+        .await
+        .unwrap()
+        .unwrap()
+        .permissions
+        .contains(&Permission::VotePoll));
 }
 
-/// Test that the caller can't vote in a poll if not allowed
+/// Test that the canister settings can be updated through a poll
 #[tokio::test]
-async fn test_caller_cant_vote_in_poll_if_not_allowed() {
+async fn test_settings_are_updated_through_poll() {
     // Arrange
     let (pocket, canister_principal) = deploy_canister(None).await;
-    let user_1_principal = Principal::from_slice(&[1u8; 29]);
-    let user_1_client = build_client(pocket.clone(), canister_principal, user_1_principal);
-    let user_2_principal = Principal::from_slice(&[2u8; 29]);
-    let user_2_client = build_client(pocket.clone(), canister_principal, user_2_principal);
     let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
 
-    let project_key = "project-10";
-    create_project(pocket.clone(), canister_principal, project_key).await;
-
     admin_client
-        .admin_permissions_add(user_1_principal, &[Permission::CreatePoll])
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll, Permission::VotePoll])
         .await
         .unwrap()
         .unwrap();
 
-    let poll = PollCreateData {
-        description: "Description".to_string(),
-        poll_type: PollType::ProjectHash {
-            project: project_key.to_string(),
-            hash: "hash".to_string(),
-        },
-        start_timestamp_secs: 0,
-        end_timestamp_secs: u64::MAX,
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let settings_poll = |patch: SettingsPatch| PollCreateData {
+        description: "Settings".to_string(),
+        poll_type: PollType::UpdateSettings(patch),
+        start_timestamp_secs: now_secs,
+        end_timestamp_secs: now_secs + 100,
     };
-    let poll_id = user_1_client.poll_create(&poll).await.unwrap().unwrap();
 
     // Act
-    assert_inspect_message_error(&user_2_client.poll_vote(poll_id, true).await);
+    let invalid_patch_result = admin_client
+        .poll_create(&settings_poll(SettingsPatch {
+            poll_min_duration_secs: Some(Some(200)),
+            poll_max_duration_secs: Some(Some(100)),
+            ..Default::default()
+        }))
+        .await
+        .unwrap();
 
-    // Permission check should fail even if the inspect message is disabled
-    {
-        disable_inspect_message(pocket, canister_principal).await;
+    let poll_id = admin_client
+        .poll_create(&settings_poll(SettingsPatch {
+            disable_inspect_message: Some(true),
+            poll_quorum: Some(1),
+            poll_min_duration_secs: Some(Some(3600)),
+            ..Default::default()
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .poll_vote(poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
 
-        // Act
-        assert!(user_2_client
-            .poll_vote(poll_id, true)
-            .await
-            .unwrap()
-            .is_err());
-    }
+    pocket.advance_time(Duration::from_secs(101)).await;
+    let closed_poll = admin_client.poll_finalize(poll_id).await.unwrap().unwrap();
 
-    // Assert
-    let poll = user_1_client
-        .poll_get_pending(poll_id)
+    let too_short_poll_result = admin_client
+        .poll_create(&settings_poll(SettingsPatch::default()))
         .await
-        .unwrap()
         .unwrap();
-    assert!(poll.yes_voters.is_empty());
-    assert!(poll.no_voters.is_empty());
+
+    // Assert
+    assert!(invalid_patch_result.is_err());
+    assert_eq!(closed_poll.result, PollResult::Accepted);
+    assert!(admin_client.is_inspect_message_disabled().await.unwrap());
+    assert_eq!(
+        admin_client.poll_settings_get().await.unwrap(),
+        PollSettings {
+            quorum: 1,
+            min_duration_secs: Some(3600),
+            max_duration_secs: None,
+        }
+    );
+    assert!(too_short_poll_result.is_err());
 }
 
-/// Test that the poll timer runs
+/// Test that the projects can be created and updated through polls
+/// and that a pending creation poll reserves the project key
 #[tokio::test]
-async fn test_poll_timer() {
+async fn test_projects_are_created_and_updated_through_polls() {
     // Arrange
     let (pocket, canister_principal) = deploy_canister(None).await;
-    let admin_principal = ADMIN;
-    let admin_client = build_client(pocket.clone(), canister_principal, admin_principal);
-
-    let project_key = "project-10";
-    create_project(pocket.clone(), canister_principal, project_key).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
 
     admin_client
         .admin_permissions_add(
-            admin_principal,
-            &[Permission::CreatePoll, Permission::VotePoll],
+            ADMIN,
+            &[
+                Permission::CreatePoll,
+                Permission::VotePoll,
+                Permission::CreateProject,
+            ],
         )
         .await
         .unwrap()
         .unwrap();
 
-    let poll = PollCreateData {
-        description: "Description".to_string(),
-        poll_type: PollType::ProjectHash {
-            project: project_key.to_string(),
-            hash: "hash".to_string(),
-        },
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let project_poll = |poll_type: PollType, end_timestamp_secs: u64| PollCreateData {
+        description: "Project".to_string(),
+        poll_type,
         start_timestamp_secs: 0,
-        end_timestamp_secs: 1,
+        end_timestamp_secs,
+    };
+    let project = ProjectData {
+        key: "project-13".to_string(),
+        name: "Project".to_string(),
+        description: "Description".to_string(),
+        canisters: None,
+    };
+    let updated_project = ProjectData {
+        name: "Updated project".to_string(),
+        ..project.clone()
     };
-    let poll_id = admin_client.poll_create(&poll).await.unwrap().unwrap();
 
     // Act
-    pocket.advance_time(POLL_TIMER_INTERVAL * 2).await;
-    pocket.tick().await;
+    let update_missing_result = admin_client
+        .poll_create(&project_poll(
+            PollType::UpdateProject(updated_project.clone()),
+            now_secs + 100,
+        ))
+        .await
+        .unwrap();
 
-    // Assert
-    let poll = admin_client
-        .poll_get_closed(poll_id)
+    let create_poll_id = admin_client
+        .poll_create(&project_poll(
+            PollType::CreateProject(project.clone()),
+            now_secs + 100,
+        ))
         .await
         .unwrap()
         .unwrap();
-    assert_eq!(poll.end_timestamp_secs, 1);
-    assert_eq!(poll.result, PollResult::Rejected);
-
+    let duplicated_create_result = admin_client
+        .poll_create(&project_poll(
+            PollType::CreateProject(project.clone()),
+            now_secs + 100,
+        ))
+        .await
+        .unwrap();
+    let direct_create_result = admin_client.project_create(&project).await.unwrap();
+    admin_client
+        .poll_vote(create_poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+
+    pocket.advance_time(Duration::from_secs(101)).await;
+    admin_client
+        .poll_finalize(create_poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+    let created_project = admin_client.project_get(&project.key).await.unwrap();
+
+    let update_poll_id = admin_client
+        .poll_create(&project_poll(
+            PollType::UpdateProject(updated_project.clone()),
+            now_secs + 300,
+        ))
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .poll_vote(update_poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+
+    pocket.advance_time(Duration::from_secs(200)).await;
+    admin_client
+        .poll_finalize(update_poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Assert
+    assert!(update_missing_result.is_err());
+    assert_eq!(
+        duplicated_create_result,
+        Err(UpgraderError::NotUniqueKey(project.key.clone()))
+    );
+    assert_eq!(
+        direct_create_result,
+        Err(UpgraderError::NotUniqueKey(project.key.clone()))
+    );
+    assert_eq!(created_project, Some(project.clone()));
+    assert_eq!(
+        admin_client.project_get(&project.key).await.unwrap(),
+        Some(updated_project)
+    );
+}
+
+/// Test that an approved project hash can be revoked by an admin in an emergency
+#[tokio::test]
+async fn test_approved_project_hash_can_be_revoked() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-14";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll, Permission::VotePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let revoke_poll = PollCreateData {
+        description: "Revoke".to_string(),
+        poll_type: PollType::RevokeProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+            reason: "vulnerability".to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: now_secs + 100,
+    };
+
+    // Act
+    let revoke_not_approved_result = admin_client.poll_create(&revoke_poll).await.unwrap();
+
+    let poll_id = admin_client
+        .poll_create(&PollCreateData {
+            description: "Approve".to_string(),
+            poll_type: PollType::ProjectHash {
+                project: project_key.to_string(),
+                hash: WASM_HASH.to_string(),
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: now_secs + 100,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .poll_vote(poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+    pocket.advance_time(Duration::from_secs(101)).await;
+    admin_client.poll_finalize(poll_id).await.unwrap().unwrap();
+
+    let status_after_approval = admin_client
+        .project_hash_status(project_key, WASM_HASH)
+        .await
+        .unwrap();
+
+    admin_client
+        .admin_project_hash_revoke(project_key, WASM_HASH, "vulnerability")
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Assert
+    assert!(revoke_not_approved_result.is_err());
+    assert_eq!(
+        status_after_approval,
+        Some(ProjectHashStatus::Approved { poll_id })
+    );
+    assert_eq!(
+        admin_client
+            .project_hash_status(project_key, WASM_HASH)
+            .await
+            .unwrap(),
+        Some(ProjectHashStatus::Revoked {
+            poll_id: None,
+            reason: "vulnerability".to_string()
+        })
+    );
+    assert_eq!(
+        admin_client
+            .project_hashes_get(project_key)
+            .await
+            .unwrap()
+            .len(),
+        1
+    );
+}
+
+/// Test that revoking a project hash requires the approval of multiple admins if enabled
+#[tokio::test]
+async fn test_project_hash_revoke_requires_multiple_admins_approval() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    let admin_2_principal = Principal::from_slice(&[2u8; 29]);
+    let admin_2_client = build_client(pocket.clone(), canister_principal, admin_2_principal);
+
+    let project_key = "project-18";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll, Permission::VotePoll])
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .admin_permissions_add(admin_2_principal, &[Permission::Admin])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let poll_id = admin_client
+        .poll_create(&PollCreateData {
+            description: "Approve".to_string(),
+            poll_type: PollType::ProjectHash {
+                project: project_key.to_string(),
+                hash: WASM_HASH.to_string(),
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: now_secs + 100,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .poll_vote(poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+    pocket.advance_time(Duration::from_secs(101)).await;
+    admin_client.poll_finalize(poll_id).await.unwrap().unwrap();
+
+    admin_client
+        .admin_approval_settings_set(Some(AdminApprovalSettings {
+            threshold: 2,
+            expiry_secs: 3600,
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    let result = admin_client
+        .admin_project_hash_revoke(project_key, &WASM_HASH.to_uppercase(), "vulnerability")
+        .await
+        .unwrap();
+
+    // Assert
+    let action_id = match result {
+        Err(UpgraderError::AdminApprovalRequired(id)) => id,
+        other => panic!("Expected AdminApprovalRequired, got {other:?}"),
+    };
+    assert_eq!(
+        admin_client
+            .project_hash_status(project_key, WASM_HASH)
+            .await
+            .unwrap(),
+        Some(ProjectHashStatus::Approved { poll_id })
+    );
+
+    let pending = admin_2_client
+        .admin_action_get_all_pending()
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        pending[&action_id].action,
+        AdminAction::RevokeProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+            reason: "vulnerability".to_string(),
+        }
+    );
+
+    assert!(admin_2_client
+        .admin_action_approve(action_id)
+        .await
+        .unwrap()
+        .unwrap());
+    assert_eq!(
+        admin_client
+            .project_hash_status(project_key, WASM_HASH)
+            .await
+            .unwrap(),
+        Some(ProjectHashStatus::Revoked {
+            poll_id: None,
+            reason: "vulnerability".to_string()
+        })
+    );
+}
+
+/// Test that the project hashes are validated and normalized when creating a poll
+#[tokio::test]
+async fn test_project_hash_is_validated_and_normalized() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-15";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let hash_poll = |hash: String| PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash,
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 999_999_999_999,
+    };
+
+    // Act
+    let truncated_result = admin_client
+        .poll_create(&hash_poll(WASM_HASH[..60].to_string()))
+        .await
+        .unwrap();
+    let not_hex_result = admin_client
+        .poll_create(&hash_poll("hash".to_string()))
+        .await
+        .unwrap();
+    let poll_id = admin_client
+        .poll_create(&hash_poll(format!("0x{}", WASM_HASH.to_uppercase())))
+        .await
+        .unwrap()
+        .unwrap();
+    let duplicated_result = admin_client
+        .poll_create(&hash_poll(WASM_HASH.to_string()))
+        .await
+        .unwrap();
+
+    // Assert
+    assert!(truncated_result.is_err());
+    assert!(not_hex_result.is_err());
+    assert!(duplicated_result.is_err());
+
+    let poll = admin_client
+        .poll_get_pending(poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        poll.poll_type,
+        PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        }
+    );
+}
+
+/// Test that the caller can't create polls if not allowed
+#[tokio::test]
+async fn test_caller_cant_create_polls_if_not_allowed() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let user_1_principal = Principal::from_slice(&[1u8; 29]);
+    let user_1_client = build_client(pocket.clone(), canister_principal, user_1_principal);
+
+    let project_key = "project-1";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    // Act
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 1,
+    };
+    assert_inspect_message_error(&user_1_client.poll_create(&poll).await);
+
+    // Permission check should fail even if the inspect message is disabled
+    {
+        disable_inspect_message(pocket, canister_principal).await;
+
+        // Act
+        assert!(user_1_client.poll_create(&poll).await.unwrap().is_err());
+    }
+
+    // Assert
+    let polls = user_1_client.poll_get_all_pending().await.unwrap();
+    assert!(polls.is_empty());
+}
+
+/// Test that the caller can vote in a poll
+#[tokio::test]
+async fn test_caller_can_vote_in_poll() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let user_1_principal = Principal::from_slice(&[1u8; 29]);
+    let user_1_client = build_client(pocket.clone(), canister_principal, user_1_principal);
+    let user_2_principal = Principal::from_slice(&[2u8; 29]);
+    let user_2_client = build_client(pocket.clone(), canister_principal, user_2_principal);
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-10";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(
+            user_1_principal,
+            &[Permission::CreatePoll, Permission::VotePoll],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .admin_permissions_add(user_2_principal, &[Permission::VotePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 999_999_999_999,
+    };
+    let poll_id = user_1_client.poll_create(&poll).await.unwrap().unwrap();
+
+    // Act
+    user_1_client
+        .poll_vote(poll_id, false)
+        .await
+        .unwrap()
+        .unwrap();
+    user_2_client
+        .poll_vote(poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Assert
+    let poll = user_1_client.poll_get(poll_id).await.unwrap().unwrap();
+
+    match poll {
+        Poll::Pending(poll) => {
+            assert_eq!(poll.yes_voters.len(), 1);
+            assert!(poll.yes_voters.contains(&user_2_principal));
+            assert_eq!(poll.no_voters.len(), 1);
+            assert!(poll.no_voters.contains(&user_1_principal));
+        }
+        _ => panic!("Poll should be pending"),
+    }
+}
+
+/// Test that the caller can't vote in a poll if not allowed
+#[tokio::test]
+async fn test_caller_cant_vote_in_poll_if_not_allowed() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let user_1_principal = Principal::from_slice(&[1u8; 29]);
+    let user_1_client = build_client(pocket.clone(), canister_principal, user_1_principal);
+    let user_2_principal = Principal::from_slice(&[2u8; 29]);
+    let user_2_client = build_client(pocket.clone(), canister_principal, user_2_principal);
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-10";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(user_1_principal, &[Permission::CreatePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 999_999_999_999,
+    };
+    let poll_id = user_1_client.poll_create(&poll).await.unwrap().unwrap();
+
+    // Act
+    assert_inspect_message_error(&user_2_client.poll_vote(poll_id, true).await);
+
+    // Permission check should fail even if the inspect message is disabled
+    {
+        disable_inspect_message(pocket, canister_principal).await;
+
+        // Act
+        assert!(user_2_client
+            .poll_vote(poll_id, true)
+            .await
+            .unwrap()
+            .is_err());
+    }
+
+    // Assert
+    let poll = user_1_client
+        .poll_get_pending(poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(poll.yes_voters.is_empty());
+    assert!(poll.no_voters.is_empty());
+}
+
+/// Test that the poll timer runs
+#[tokio::test]
+async fn test_poll_timer() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_principal = ADMIN;
+    let admin_client = build_client(pocket.clone(), canister_principal, admin_principal);
+
+    let project_key = "project-10";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(
+            admin_principal,
+            &[Permission::CreatePoll, Permission::VotePoll],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 1,
+    };
+    let poll_id = admin_client.poll_create(&poll).await.unwrap().unwrap();
+
+    // Act
+    pocket.advance_time(POLL_TIMER_INTERVAL * 2).await;
+    pocket.tick().await;
+
+    // Assert
+    let poll = admin_client
+        .poll_get_closed(poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(poll.end_timestamp_secs, 1);
+    assert_eq!(poll.result, PollResult::Rejected);
+
     assert!(admin_client
         .poll_get_all_pending()
         .await
@@ -597,12 +1324,470 @@ async fn test_poll_timer() {
     assert!(!admin_client.poll_get_all_closed().await.unwrap().is_empty());
 }
 
+/// Test that a poll is finalized right after its end time
+#[tokio::test]
+async fn test_poll_is_finalized_at_end_time() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-10";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: now_secs + 10,
+    };
+    let poll_id = admin_client.poll_create(&poll).await.unwrap().unwrap();
+
+    // Act
+    let closed_poll = admin_client
+        .wait_for_poll_result_with(poll_id, Duration::from_secs(60), |delay| {
+            let pocket = pocket.clone();
+            async move {
+                pocket.advance_time(delay).await;
+                pocket.tick().await;
+                pocket.tick().await;
+            }
+        })
+        .await
+        .unwrap();
+
+    // Assert
+    assert_eq!(closed_poll.end_timestamp_secs, now_secs + 10);
+    assert_eq!(closed_poll.result, PollResult::Rejected);
+}
+
+/// Test that waiting for the result of a poll that is not closed in time fails
+#[tokio::test]
+async fn test_wait_for_poll_result_timeout() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-10";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 999_999_999_999,
+    };
+    let poll_id = admin_client.poll_create(&poll).await.unwrap().unwrap();
+
+    // Act
+    let timeout_result = admin_client
+        .wait_for_poll_result_with(poll_id, Duration::from_secs(1), |delay| {
+            let pocket = pocket.clone();
+            async move { pocket.advance_time(delay).await }
+        })
+        .await;
+    let not_found_result = admin_client
+        .wait_for_poll_result(poll_id + 1, Duration::from_secs(1))
+        .await;
+
+    // Assert
+    assert!(matches!(timeout_result, Err(WaitPollError::Timeout(id)) if id == poll_id));
+    assert!(matches!(not_found_result, Err(WaitPollError::NotFound(_))));
+}
+
+/// Test that any caller can finalize the polls past their end time
+#[tokio::test]
+async fn test_any_caller_can_finalize_polls() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    let user_client = build_client(
+        pocket.clone(),
+        canister_principal,
+        Principal::from_slice(&[1u8; 29]),
+    );
+
+    let project_key = "project-11";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: now_secs + 100,
+    };
+    let poll_id = admin_client.poll_create(&poll).await.unwrap().unwrap();
+
+    // Act
+    let not_ended_result = user_client.poll_finalize(poll_id).await.unwrap();
+    let not_ended_due = user_client.polls_finalize_due().await.unwrap().unwrap();
+
+    pocket.advance_time(Duration::from_secs(101)).await;
+    user_client.polls_finalize_due().await.unwrap().unwrap();
+
+    // Assert
+    assert_eq!(
+        not_ended_result,
+        Err(UpgraderError::PollNotEnded {
+            ends_at: now_secs + 100
+        })
+    );
+    assert!(not_ended_due.is_empty());
+    assert!(admin_client
+        .poll_get_closed(poll_id)
+        .await
+        .unwrap()
+        .is_some());
+    assert!(user_client.poll_finalize(poll_id).await.unwrap().is_err());
+}
+
+/// Test that the admin can change the poll timer interval
+#[tokio::test]
+async fn test_admin_can_set_poll_timer_interval() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    let user_client = build_client(
+        pocket,
+        canister_principal,
+        Principal::from_slice(&[1u8; 29]),
+    );
+
+    // Act
+    let interval_before = admin_client.poll_timer_interval_get().await.unwrap();
+    admin_client
+        .admin_poll_timer_interval_set(60)
+        .await
+        .unwrap()
+        .unwrap();
+    let interval_after = admin_client.poll_timer_interval_get().await.unwrap();
+
+    // Assert
+    assert_eq!(interval_before, POLL_TIMER_INTERVAL.as_secs());
+    assert_eq!(interval_after, 60);
+    assert!(admin_client
+        .admin_poll_timer_interval_set(0)
+        .await
+        .unwrap()
+        .is_err());
+    assert_inspect_message_error(&user_client.admin_poll_timer_interval_set(30).await);
+}
+
+/// Test that the closed polls are moved to the archive canister
+#[tokio::test]
+async fn test_closed_polls_are_moved_to_archive() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let archive_principal = deploy_archive_canister(&pocket, canister_principal).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    let archive_client = build_archive_client(pocket.clone(), archive_principal, ADMIN);
+
+    let project_key = "project-10";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .admin_archive_settings_set(Some(ArchiveSettings {
+            canister_id: archive_principal,
+            retention_secs: 0,
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let poll = PollCreateData {
+        description: "Description".to_string(),
+        poll_type: PollType::ProjectHash {
+            project: project_key.to_string(),
+            hash: WASM_HASH.to_string(),
+        },
+        start_timestamp_secs: 0,
+        end_timestamp_secs: 1,
+    };
+    let poll_id = admin_client.poll_create(&poll).await.unwrap().unwrap();
+
+    pocket.advance_time(POLL_TIMER_INTERVAL * 2).await;
+    pocket.tick().await;
+    let closed_poll = admin_client
+        .poll_get_closed(poll_id)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Act
+    pocket.advance_time(ARCHIVE_TIMER_INTERVAL).await;
+    for _ in 0..5 {
+        pocket.tick().await;
+    }
+
+    // Assert
+    assert_eq!(
+        admin_client.poll_get(poll_id).await.unwrap(),
+        Some(Poll::Archived {
+            archive_canister: archive_principal
+        })
+    );
+    assert_eq!(
+        admin_client
+            .poll_get_archive_canister(poll_id)
+            .await
+            .unwrap(),
+        Some(archive_principal)
+    );
+    assert!(admin_client
+        .poll_get_closed(poll_id)
+        .await
+        .unwrap()
+        .is_none());
+    assert!(admin_client.poll_get_all_closed().await.unwrap().is_empty());
+
+    assert_eq!(
+        archive_client.poll_get(poll_id).await.unwrap(),
+        Some(closed_poll)
+    );
+    assert_eq!(archive_client.poll_count().await.unwrap(), 1);
+}
+
+/// Test that only the upgrader canister can append polls to the archive
+#[tokio::test]
+async fn test_only_upgrader_can_append_polls_to_archive() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let archive_principal = deploy_archive_canister(&pocket, canister_principal).await;
+    let archive_client = build_archive_client(pocket, archive_principal, ADMIN);
+
+    // Act
+    let result = archive_client.polls_append(&[]).await.unwrap();
+
+    // Assert
+    assert_eq!(result, Err(UpgraderError::NotAuthorized));
+    assert_eq!(
+        archive_client.upgrader_get().await.unwrap(),
+        canister_principal
+    );
+}
+
 fn assert_inspect_message_error<T: std::fmt::Debug>(result: &CanisterClientResult<T>) {
     assert!(result.is_err());
     let error = result.as_ref().unwrap_err();
     assert!(error.to_string().contains("Call rejected by inspect check"));
 }
 
+/// Test that the deployment status of the project canisters is reported
+#[tokio::test]
+async fn test_project_deployment_status() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-16";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    // The upgrader canister itself is linked to the project
+    let canister = ProjectCanister {
+        network: "local".to_string(),
+        canister_id: canister_principal,
+    };
+    // A canister deployed on another network is not inspected
+    let foreign_canister = ProjectCanister {
+        network: "ic".to_string(),
+        canister_id: Principal::from_slice(&[33u8; 29]),
+    };
+    link_project_canisters(
+        pocket.clone(),
+        canister_principal,
+        project_key,
+        vec![canister.clone(), foreign_canister.clone()],
+    )
+    .await;
+
+    let unauthorized_client = build_client(
+        pocket.clone(),
+        canister_principal,
+        Principal::from_slice(&[34u8; 29]),
+    );
+
+    // Act
+    let status = admin_client
+        .project_deployment_status(project_key)
+        .await
+        .unwrap()
+        .unwrap();
+    let unknown_project_result = admin_client
+        .project_deployment_status("unknown")
+        .await
+        .unwrap();
+    let unauthorized_result = unauthorized_client
+        .project_deployment_status(project_key)
+        .await;
+
+    // Assert
+    assert_eq!(status.project, project_key);
+    assert_eq!(status.latest_approved_hash, None);
+    assert_eq!(status.canisters.len(), 2);
+    assert_eq!(status.canisters[0].canister, canister);
+    assert!(status.canisters[0].module_hash.is_some());
+    assert_eq!(status.canisters[0].status, DeploymentStatus::Unapproved);
+    assert_eq!(status.canisters[1].canister, foreign_canister);
+    assert_eq!(status.canisters[1].module_hash, None);
+    assert!(matches!(
+        status.canisters[1].status,
+        DeploymentStatus::Unknown(_)
+    ));
+
+    assert_eq!(
+        unknown_project_result,
+        Err(UpgraderError::ProjectNotFound("unknown".to_string()))
+    );
+    assert_inspect_message_error(&unauthorized_result);
+}
+
+/// Test that the installation of a module that was never approved is alerted
+#[tokio::test]
+async fn test_unapproved_upgrade_is_alerted() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister(None).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let project_key = "project-17";
+    create_project(pocket.clone(), canister_principal, project_key).await;
+
+    // The upgrader canister itself is linked to the project
+    let canister = ProjectCanister {
+        network: "local".to_string(),
+        canister_id: canister_principal,
+    };
+    // The same canister listed on another network must not be checked
+    let foreign_canister = ProjectCanister {
+        network: "ic".to_string(),
+        canister_id: canister_principal,
+    };
+    link_project_canisters(
+        pocket.clone(),
+        canister_principal,
+        project_key,
+        vec![canister.clone(), foreign_canister],
+    )
+    .await;
+
+    // Act
+    pocket.advance_time(ALERTS_TIMER_INTERVAL).await;
+    for _ in 0..5 {
+        pocket.tick().await;
+    }
+    let alerts_after_first_check = admin_client.alerts_get(0, 10).await.unwrap();
+
+    pocket.advance_time(ALERTS_TIMER_INTERVAL).await;
+    for _ in 0..5 {
+        pocket.tick().await;
+    }
+
+    // Assert
+    let status = admin_client
+        .project_deployment_status(project_key)
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(alerts_after_first_check.len(), 1);
+    let alert = &alerts_after_first_check[&0];
+    assert_eq!(alert.project, project_key);
+    assert_eq!(alert.canister, canister);
+    assert_eq!(
+        Some(&alert.module_hash),
+        status.canisters[0].module_hash.as_ref()
+    );
+
+    // The installation is alerted only once
+    assert_eq!(admin_client.alerts_len().await.unwrap(), 1);
+}
+
+/// Links the canisters to a project through an accepted `UpdateProject` poll
+async fn link_project_canisters(
+    pocket: Arc<PocketIc>,
+    canister_principal: Principal,
+    project_key: &str,
+    canisters: Vec<ProjectCanister>,
+) {
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+    admin_client
+        .admin_permissions_add(ADMIN, &[Permission::CreatePoll, Permission::VotePoll])
+        .await
+        .unwrap()
+        .unwrap();
+
+    let now_secs = pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let poll_id = admin_client
+        .poll_create(&PollCreateData {
+            description: "Link the canisters".to_string(),
+            poll_type: PollType::UpdateProject(ProjectData {
+                key: project_key.to_string(),
+                name: format!("Project {}", project_key),
+                description: format!("Description {}", project_key),
+                canisters: Some(canisters),
+            }),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: now_secs + 100,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    admin_client
+        .poll_vote(poll_id, true)
+        .await
+        .unwrap()
+        .unwrap();
+    pocket.advance_time(Duration::from_secs(101)).await;
+    admin_client.poll_finalize(poll_id).await.unwrap().unwrap();
+}
+
 async fn disable_inspect_message(pocket: Arc<PocketIc>, canister_principal: Principal) {
     let admin_client = build_client(pocket, canister_principal, ADMIN);
     admin_client
@@ -628,6 +1813,7 @@ async fn create_project(pocket: Arc<PocketIc>, canister_principal: Principal, pr
         key: project_key.to_string(),
         name: format!("Project {}", project_key),
         description: format!("Description {}", project_key),
+        canisters: None,
     };
     user_1_client
         .project_create(&project)
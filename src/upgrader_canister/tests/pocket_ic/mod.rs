@@ -3,9 +3,9 @@ use std::sync::Arc;
 use candid::Principal;
 use ic_canister_client::PocketIcClient;
 use ic_exports::pocket_ic::PocketIc;
-use upgrader_canister_client::UpgraderCanisterClient;
-use upgrader_canister_did::UpgraderCanisterInitData;
-use wasm_utils::get_upgrader_canister_bytecode;
+use upgrader_canister_client::{ArchiveCanisterClient, UpgraderCanisterClient};
+use upgrader_canister_did::{ArchiveCanisterInitData, UpgraderCanisterInitData};
+use wasm_utils::{get_archive_canister_bytecode, get_upgrader_canister_bytecode};
 
 pub mod wasm_utils;
 
@@ -13,19 +13,48 @@ pub const ADMIN: Principal = Principal::from_slice(&[212u8; 29]);
 
 /// Deploys the upgrader canister and returns its principal
 pub async fn deploy_canister(env: Option<PocketIc>) -> (Arc<PocketIc>, Principal) {
+    deploy_canister_wasm(env, get_upgrader_canister_bytecode()).await
+}
+
+/// Deploys the given wasm of the upgrader canister and returns its principal
+pub async fn deploy_canister_wasm(
+    env: Option<PocketIc>,
+    wasm: Vec<u8>,
+) -> (Arc<PocketIc>, Principal) {
     let env = if let Some(env) = env {
         env
     } else {
         ic_exports::pocket_ic::init_pocket_ic().await
     };
-    let wasm = get_upgrader_canister_bytecode();
-    let init_data = UpgraderCanisterInitData { admin: ADMIN };
+    let init_data = UpgraderCanisterInitData {
+        admin: ADMIN,
+        network: Some("local".to_string()),
+    };
+    let args = candid::encode_args((init_data,)).unwrap();
+    let canister = env.create_canister().await;
+    env.add_cycles(canister, 10_u128.pow(12)).await;
+    env.install_canister(canister, wasm, args, None).await;
+    (Arc::new(env), canister)
+}
+
+/// Upgrades the upgrader canister to the given wasm
+pub async fn upgrade_canister(env: &PocketIc, canister: Principal, wasm: Vec<u8>) {
+    let args = candid::encode_args(()).unwrap();
+    env.upgrade_canister(canister, wasm, args, None)
+        .await
+        .expect("the upgrader canister upgrade failed");
+}
+
+/// Deploys an archive canister for the given upgrader canister and returns its principal
+pub async fn deploy_archive_canister(env: &PocketIc, upgrader: Principal) -> Principal {
+    let wasm = get_archive_canister_bytecode();
+    let init_data = ArchiveCanisterInitData { upgrader };
     let args = candid::encode_args((init_data,)).unwrap();
     let canister = env.create_canister().await;
     env.add_cycles(canister, 10_u128.pow(12)).await;
     env.install_canister(canister, wasm.to_vec(), args, None)
         .await;
-    (Arc::new(env), canister)
+    canister
 }
 
 /// Builds an upgrader canister client
@@ -37,3 +66,13 @@ pub fn build_client(
     let client = PocketIcClient::from_client(pocket, canister_principal, caller_principal);
     UpgraderCanisterClient::new(client)
 }
+
+/// Builds an archive canister client
+pub fn build_archive_client(
+    pocket: Arc<PocketIc>,
+    canister_principal: Principal,
+    caller_principal: Principal,
+) -> ArchiveCanisterClient<PocketIcClient> {
+    let client = PocketIcClient::from_client(pocket, canister_principal, caller_principal);
+    ArchiveCanisterClient::new(client)
+}
@@ -11,6 +11,21 @@ pub fn get_upgrader_canister_bytecode() -> Vec<u8> {
         .to_owned()
 }
 
+/// Returns the bytecode of the archive canister
+pub fn get_archive_canister_bytecode() -> Vec<u8> {
+    static CANISTER_BYTECODE: OnceLock<Vec<u8>> = OnceLock::new();
+    CANISTER_BYTECODE
+        .get_or_init(|| load_wasm_bytecode_or_panic("archive_canister.wasm.gz"))
+        .to_owned()
+}
+
+/// Returns the bytecode of the previous release of the canister, if it was fetched
+/// in the fixtures directory with `scripts/fetch_previous_release.sh`
+pub fn get_previous_upgrader_canister_bytecode() -> Option<Vec<u8>> {
+    const PREVIOUS_RELEASE_PATH: &str = "tests/fixtures/upgrader_canister_previous.wasm.gz";
+    std::fs::read(PREVIOUS_RELEASE_PATH).ok()
+}
+
 fn load_wasm_bytecode_or_panic(wasm_name: &str) -> Vec<u8> {
     let path = get_path_to_wasm(wasm_name);
 
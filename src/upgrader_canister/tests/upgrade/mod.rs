@@ -0,0 +1,224 @@
+use std::collections::BTreeMap;
+use std::time::{Duration, UNIX_EPOCH};
+
+use candid::Principal;
+use ic_canister_client::PocketIcClient;
+use ic_exports::pocket_ic::PocketIc;
+use upgrader_canister::constant::POLL_TIMER_INTERVAL;
+use upgrader_canister_client::UpgraderCanisterClient;
+use upgrader_canister_did::{
+    ClosedPoll, PendingPoll, Permission, PermissionList, Poll, PollCreateData, PollResult,
+    PollType, ProjectData,
+};
+
+use crate::pocket_ic::wasm_utils::{
+    get_previous_upgrader_canister_bytecode, get_upgrader_canister_bytecode,
+};
+use crate::pocket_ic::{build_client, deploy_canister_wasm, upgrade_canister, ADMIN};
+
+/// A valid wasm hash used in the project hash polls
+const WASM_HASH: &str = "c1a2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90";
+
+/// The data returned by the queries available in every release
+#[derive(Debug, PartialEq)]
+struct StateSnapshot {
+    permissions: Vec<PermissionList>,
+    is_inspect_message_disabled: bool,
+    projects: Vec<ProjectData>,
+    pending_polls: BTreeMap<u64, PendingPoll>,
+    closed_polls: BTreeMap<u64, ClosedPoll>,
+    polls: Vec<Option<Poll>>,
+}
+
+impl StateSnapshot {
+    async fn take(
+        client: &UpgraderCanisterClient<PocketIcClient>,
+        principals: &[Principal],
+        poll_ids: &[u64],
+    ) -> Self {
+        let mut permissions = vec![];
+        for principal in principals {
+            permissions.push(
+                client
+                    .admin_permissions_get(*principal)
+                    .await
+                    .unwrap()
+                    .unwrap(),
+            );
+        }
+
+        let mut polls = vec![];
+        for poll_id in poll_ids {
+            polls.push(client.poll_get(*poll_id).await.unwrap());
+        }
+
+        Self {
+            permissions,
+            is_inspect_message_disabled: client.is_inspect_message_disabled().await.unwrap(),
+            projects: client.project_get_all().await.unwrap(),
+            pending_polls: client.poll_get_all_pending().await.unwrap(),
+            closed_polls: client.poll_get_all_closed().await.unwrap(),
+            polls,
+        }
+    }
+}
+
+/// Returns the wasm of the previous release.
+/// Panics if it was not fetched, as upgrading the current build to itself proves nothing.
+fn previous_release_wasm() -> Vec<u8> {
+    get_previous_upgrader_canister_bytecode().expect(
+        "The previous release wasm was not found in tests/fixtures, \
+        run ./scripts/fetch_previous_release.sh before the tests",
+    )
+}
+
+async fn current_time_secs(pocket: &PocketIc) -> u64 {
+    pocket
+        .get_time()
+        .await
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Test that the state is preserved when upgrading from the previous release
+/// and that the timers finalize the polls after the upgrade.
+/// Only the endpoints available in every release are used before the upgrade.
+#[tokio::test]
+async fn test_state_is_preserved_on_upgrade_from_previous_release() {
+    // Arrange
+    let (pocket, canister_principal) = deploy_canister_wasm(None, previous_release_wasm()).await;
+    let admin_client = build_client(pocket.clone(), canister_principal, ADMIN);
+
+    let voter_1 = Principal::from_slice(&[31u8; 29]);
+    let voter_2 = Principal::from_slice(&[32u8; 29]);
+    let principals = [ADMIN, voter_1, voter_2];
+
+    admin_client
+        .admin_permissions_add(
+            ADMIN,
+            &[
+                Permission::CreateProject,
+                Permission::CreatePoll,
+                Permission::VotePoll,
+            ],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+    for voter in [voter_1, voter_2] {
+        admin_client
+            .admin_permissions_add(voter, &[Permission::VotePoll])
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    let project_key = "upgrade-project";
+    admin_client
+        .project_create(&ProjectData {
+            key: project_key.to_string(),
+            name: "Upgrade project".to_string(),
+            description: "A project created before the upgrade".to_string(),
+            canisters: None,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    let now_secs = current_time_secs(&pocket).await;
+    let ending_poll_id = admin_client
+        .poll_create(&PollCreateData {
+            description: "A poll ending before the upgrade".to_string(),
+            poll_type: PollType::AddPermission {
+                principals: vec![voter_2],
+                permissions: vec![Permission::CreatePoll],
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: now_secs + 10,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let pending_poll_id = admin_client
+        .poll_create(&PollCreateData {
+            description: "A poll ending after the upgrade".to_string(),
+            poll_type: PollType::ProjectHash {
+                project: project_key.to_string(),
+                hash: WASM_HASH.to_string(),
+            },
+            start_timestamp_secs: 0,
+            end_timestamp_secs: now_secs + 1_000,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+    let poll_ids = [ending_poll_id, pending_poll_id];
+
+    for voter in [voter_1, voter_2] {
+        let voter_client = build_client(pocket.clone(), canister_principal, voter);
+        for poll_id in poll_ids {
+            voter_client
+                .poll_vote(poll_id, true)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    }
+    admin_client
+        .poll_vote(pending_poll_id, false)
+        .await
+        .unwrap()
+        .unwrap();
+
+    // let the previous release finalize the first poll, if it has a timer
+    advance_time(&pocket, Duration::from_secs(20)).await;
+
+    let snapshot_before_upgrade = StateSnapshot::take(&admin_client, &principals, &poll_ids).await;
+
+    // Act
+    upgrade_canister(
+        &pocket,
+        canister_principal,
+        get_upgrader_canister_bytecode(),
+    )
+    .await;
+
+    // Assert
+    let snapshot_after_upgrade = StateSnapshot::take(&admin_client, &principals, &poll_ids).await;
+    assert_eq!(snapshot_before_upgrade, snapshot_after_upgrade);
+
+    advance_time(
+        &pocket,
+        Duration::from_secs(1_000) + POLL_TIMER_INTERVAL * 2,
+    )
+    .await;
+
+    assert!(admin_client
+        .poll_get_all_pending()
+        .await
+        .unwrap()
+        .is_empty());
+    for poll_id in poll_ids {
+        let poll = admin_client
+            .poll_get_closed(poll_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(poll.result, PollResult::Accepted);
+    }
+    assert!(admin_client
+        .admin_permissions_get(voter_2)
+        .await
+        .unwrap()
+        .unwrap()
+        .permissions
+        .contains(&Permission::CreatePoll));
+}
+
+/// Advances the time and lets the timers run
+async fn advance_time(pocket: &PocketIc, duration: Duration) {
+    pocket.advance_time(duration).await;
+    pocket.tick().await;
+    pocket.tick().await;
+}
@@ -1,2 +1,3 @@
 pub mod canister;
 pub mod pocket_ic;
+pub mod upgrade;
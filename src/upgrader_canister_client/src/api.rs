@@ -0,0 +1,400 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Duration;
+
+use candid::Principal;
+use ic_canister_client::{CanisterClient, CanisterClientError, CanisterClientResult};
+use ic_exports::ic_cdk::api::call::RejectionCode;
+use upgrader_canister_did::error::Result;
+use upgrader_canister_did::{
+    AdminApprovalSettings, ArchiveSettings, AuditLogEntry, BuildData, ClosedPoll, HttpRequest,
+    HttpResponse, PendingAdminAction, PendingPoll, Permission, PermissionList, Poll,
+    PollCreateData, PollSettings, ProjectData, ProjectDeploymentStatus, ProjectHashStatus,
+    UnapprovedUpgradeAlert,
+};
+
+#[cfg(feature = "mock")]
+use crate::MockUpgrader;
+use crate::UpgraderCanisterClient;
+
+/// Declares the `UpgraderApi` trait and implements it for the clients and the decorators.
+/// Each endpoint is listed once, with the type returned by the canister.
+macro_rules! upgrader_api {
+    ($(
+        $(#[$doc:meta])*
+        fn $name:ident(&self $(, $arg:ident: $ty:ty)*) -> $ret:ty;
+    )*) => {
+        /// The names of the endpoints of the `UpgraderApi`
+        pub const UPGRADER_API_METHODS: &[&str] = &[$(stringify!($name)),*];
+
+        /// The API of the upgrader canister.
+        ///
+        /// It is implemented by `UpgraderCanisterClient` and by the decorators adding retries
+        /// and logging, so that the dependent code can swap the implementation.
+        #[allow(async_fn_in_trait)]
+        pub trait UpgraderApi {
+            $(
+                $(#[$doc])*
+                async fn $name(&self $(, $arg: $ty)*) -> CanisterClientResult<$ret>;
+            )*
+        }
+
+        impl<C: CanisterClient> UpgraderApi for UpgraderCanisterClient<C> {
+            $(
+                async fn $name(&self $(, $arg: $ty)*) -> CanisterClientResult<$ret> {
+                    UpgraderCanisterClient::$name(self $(, $arg)*).await
+                }
+            )*
+        }
+
+        #[cfg(feature = "mock")]
+        impl UpgraderApi for MockUpgrader {
+            $(
+                async fn $name(&self $(, $arg: $ty)*) -> CanisterClientResult<$ret> {
+                    MockUpgrader::$name(self $(, $arg)*).await
+                }
+            )*
+        }
+
+        #[cfg(feature = "tokio")]
+        impl<A: UpgraderApi> UpgraderApi for RetryUpgrader<A> {
+            $(
+                async fn $name(&self $(, $arg: $ty)*) -> CanisterClientResult<$ret> {
+                    self.retry(stringify!($name), || self.inner.$name($($arg),*)).await
+                }
+            )*
+        }
+
+        impl<A: UpgraderApi> UpgraderApi for LoggingUpgrader<A> {
+            $(
+                async fn $name(&self $(, $arg: $ty)*) -> CanisterClientResult<$ret> {
+                    log_call(stringify!($name), self.inner.$name($($arg),*)).await
+                }
+            )*
+        }
+    };
+}
+
+upgrader_api! {
+    /// Returns the build data of the canister
+    fn canister_build_data(&self) -> BuildData;
+    /// Sends a request to the read-only governance dashboard
+    fn http_request(&self, request: &HttpRequest) -> HttpResponse;
+    /// Returns the permissions of a principal
+    fn admin_permissions_get(&self, principal: Principal) -> Result<PermissionList>;
+    /// Adds permissions to a principal and returns the principal permissions
+    fn admin_permissions_add(
+        &self,
+        principal: Principal,
+        permissions: &[Permission]
+    ) -> Result<PermissionList>;
+    /// Removes permissions from a principal and returns the principal permissions
+    fn admin_permissions_remove(
+        &self,
+        principal: Principal,
+        permissions: &[Permission]
+    ) -> Result<PermissionList>;
+    /// Disable/Enable the inspect message
+    fn admin_disable_inspect_message(&self, value: bool) -> Result<()>;
+    /// Sets the multi-admin approval settings
+    fn admin_approval_settings_set(
+        &self,
+        approval: Option<AdminApprovalSettings>
+    ) -> Result<()>;
+    /// Sets the archive settings
+    fn admin_archive_settings_set(&self, archive: Option<ArchiveSettings>) -> Result<()>;
+    /// Sets the interval in seconds at which the poll timer finalizes the polls
+    fn admin_poll_timer_interval_set(&self, interval_secs: u64) -> Result<()>;
+    /// Returns the interval in seconds at which the poll timer finalizes the polls
+    fn poll_timer_interval_get(&self) -> u64;
+    /// Returns the settings applied to the polls
+    fn poll_settings_get(&self) -> PollSettings;
+    /// Returns the archive settings
+    fn archive_settings_get(&self) -> Option<ArchiveSettings>;
+    /// Returns the multi-admin approval settings
+    fn admin_approval_settings_get(&self) -> Option<AdminApprovalSettings>;
+    /// Returns all the pending admin actions that are not expired
+    fn admin_action_get_all_pending(&self) -> Result<BTreeMap<u64, PendingAdminAction>>;
+    /// Approves a pending admin action and returns true if the action was executed
+    fn admin_action_approve(&self, id: u64) -> Result<bool>;
+    /// Returns the audit log entries starting from the `start` index
+    fn audit_log_get(&self, start: u64, limit: u64) -> Result<BTreeMap<u64, AuditLogEntry>>;
+    /// Returns the number of entries in the audit log
+    fn audit_log_len(&self) -> Result<u64>;
+    /// Returns the alerts of unapproved upgrades starting from the `start` index
+    fn alerts_get(&self, start: u64, limit: u64) -> BTreeMap<u64, UnapprovedUpgradeAlert>;
+    /// Returns the number of recorded alerts of unapproved upgrades
+    fn alerts_len(&self) -> u64;
+    /// Returns whether the inspect message is disabled
+    fn is_inspect_message_disabled(&self) -> bool;
+    /// Returns the permissions of the caller
+    fn caller_permissions_get(&self) -> Result<PermissionList>;
+    /// Returns all projects
+    fn project_get_all(&self) -> Vec<ProjectData>;
+    /// Returns a project by key
+    fn project_get(&self, key: &str) -> Option<ProjectData>;
+    /// Returns the status of a project hash, if it was ever approved
+    fn project_hash_status(&self, project: &str, hash: &str) -> Option<ProjectHashStatus>;
+    /// Returns the status of all the approved and revoked hashes of a project
+    fn project_hashes_get(&self, project: &str) -> BTreeMap<String, ProjectHashStatus>;
+    /// Returns the deployment status of the canisters of a project
+    fn project_deployment_status(&self, key: &str) -> Result<ProjectDeploymentStatus>;
+    /// Revokes an approved project hash without waiting for a poll
+    fn admin_project_hash_revoke(&self, project: &str, hash: &str, reason: &str) -> Result<()>;
+    /// Creates a new project
+    fn project_create(&self, project: &ProjectData) -> Result<()>;
+    /// Returns all pending polls
+    fn poll_get_all_pending(&self) -> BTreeMap<u64, PendingPoll>;
+    /// Returns all closed polls
+    fn poll_get_all_closed(&self) -> BTreeMap<u64, ClosedPoll>;
+    /// Returns a poll by id
+    fn poll_get(&self, id: u64) -> Option<Poll>;
+    /// Returns the id of the archive canister where the poll was moved, if it was archived
+    fn poll_get_archive_canister(&self, id: u64) -> Option<Principal>;
+    /// Returns a poll by id searching in the pending polls
+    fn poll_get_pending(&self, id: u64) -> Option<PendingPoll>;
+    /// Returns a poll by id searching in the closed polls
+    fn poll_get_closed(&self, id: u64) -> Option<ClosedPoll>;
+    /// Creates a new poll and returns the generated poll id
+    fn poll_create(&self, poll: &PollCreateData) -> Result<u64>;
+    /// Votes for a poll. If the voter has already voted, the previous vote is replaced.
+    fn poll_vote(&self, poll_id: u64, approved: bool) -> Result<()>;
+    /// Finalizes a poll whose end timestamp is passed and returns the closed poll
+    fn poll_finalize(&self, id: u64) -> Result<ClosedPoll>;
+    /// Finalizes all the polls whose end timestamp is passed and returns the closed polls
+    fn polls_finalize_due(&self) -> Result<BTreeMap<u64, ClosedPoll>>;
+}
+
+/// The retry policy of `RetryUpgrader`
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts of a call, including the first one
+    pub max_attempts: u32,
+    /// The delay before the first retry, doubled at each following retry
+    pub initial_delay: Duration,
+    /// The maximum delay between two attempts
+    pub max_delay: Duration,
+    /// Returns true if the call failed with a transient error and can be retried
+    pub is_transient: fn(&CanisterClientError) -> bool,
+    /// Whether the endpoints listed in `NON_IDEMPOTENT_METHODS` are retried as well
+    pub retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            is_transient: is_transient_error,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+/// The update endpoints that must not be retried by default.
+/// A call failed with a transient error may have been executed anyway, and executing these
+/// endpoints twice creates a duplicated poll, project or pending admin action,
+/// or returns an error for an action that was actually executed.
+pub const NON_IDEMPOTENT_METHODS: &[&str] = &[
+    "admin_permissions_add",
+    "admin_permissions_remove",
+    "admin_disable_inspect_message",
+    "admin_approval_settings_set",
+    "admin_archive_settings_set",
+    "admin_poll_timer_interval_set",
+    "admin_action_approve",
+    "admin_project_hash_revoke",
+    "project_create",
+    "poll_create",
+    "poll_finalize",
+];
+
+/// Returns true if the call was rejected with the `SysTransient` rejection code.
+/// The errors of the agent or of pocket-ic are not considered transient,
+/// a custom `RetryPolicy::is_transient` can be set to retry them.
+pub fn is_transient_error(err: &CanisterClientError) -> bool {
+    matches!(
+        err,
+        CanisterClientError::CanisterError((RejectionCode::SysTransient, _))
+    )
+}
+
+/// Decorates an `UpgraderApi` retrying the calls failed with a transient error.
+///
+/// Only the queries and the idempotent updates are retried, unless
+/// `RetryPolicy::retry_non_idempotent` is set: see `NON_IDEMPOTENT_METHODS`.
+/// It waits between the attempts with the tokio timer, so it requires the `tokio` feature.
+#[cfg(feature = "tokio")]
+#[derive(Debug, Clone)]
+pub struct RetryUpgrader<A> {
+    inner: A,
+    policy: RetryPolicy,
+}
+
+#[cfg(feature = "tokio")]
+impl<A: UpgraderApi> RetryUpgrader<A> {
+    /// Decorates an `UpgraderApi` with the given retry policy
+    pub fn new(inner: A, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+
+    /// Returns the decorated `UpgraderApi`
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<A> RetryUpgrader<A> {
+    /// Executes a call, retrying it while it fails with a transient error
+    async fn retry<T, F, Fut>(&self, method: &str, mut call: F) -> CanisterClientResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = CanisterClientResult<T>>,
+    {
+        let max_attempts =
+            if self.policy.retry_non_idempotent || !NON_IDEMPOTENT_METHODS.contains(&method) {
+                self.policy.max_attempts
+            } else {
+                1
+            };
+        let mut attempt = 1;
+        let mut delay = self.policy.initial_delay;
+
+        loop {
+            match call().await {
+                Err(err) if attempt < max_attempts && (self.policy.is_transient)(&err) => {
+                    log::warn!(
+                        "{method}: transient error on attempt {attempt} of {max_attempts}: {err}"
+                    );
+                    tokio::time::sleep(delay).await;
+                    delay = (delay * 2).min(self.policy.max_delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// Decorates an `UpgraderApi` logging the calls and their outcome
+#[derive(Debug, Clone)]
+pub struct LoggingUpgrader<A> {
+    inner: A,
+}
+
+impl<A: UpgraderApi> LoggingUpgrader<A> {
+    /// Decorates an `UpgraderApi` logging its calls
+    pub fn new(inner: A) -> Self {
+        Self { inner }
+    }
+
+    /// Returns the decorated `UpgraderApi`
+    pub fn inner(&self) -> &A {
+        &self.inner
+    }
+}
+
+/// Awaits a call and logs its outcome
+async fn log_call<T>(
+    method: &str,
+    call: impl Future<Output = CanisterClientResult<T>>,
+) -> CanisterClientResult<T> {
+    log::debug!("calling {method}");
+    let result = call.await;
+    match &result {
+        Ok(_) => log::debug!("{method} completed"),
+        Err(err) => log::warn!("{method} failed: {err}"),
+    }
+    result
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod test {
+
+    use std::cell::Cell;
+
+    use super::*;
+
+    fn policy(retry_non_idempotent: bool) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            is_transient: is_transient_error,
+            retry_non_idempotent,
+        }
+    }
+
+    fn transient_error() -> CanisterClientError {
+        CanisterClientError::CanisterError((RejectionCode::SysTransient, "busy".to_string()))
+    }
+
+    /// Calls a method always failing with the given error and returns the number of attempts
+    async fn attempts(
+        policy: RetryPolicy,
+        method: &str,
+        error: fn() -> CanisterClientError,
+    ) -> u32 {
+        let retry = RetryUpgrader { inner: (), policy };
+        let attempts = Cell::new(0);
+
+        let result: CanisterClientResult<()> = retry
+            .retry(method, || async {
+                attempts.set(attempts.get() + 1);
+                Err(error())
+            })
+            .await;
+
+        assert!(result.is_err());
+        attempts.get()
+    }
+
+    #[tokio::test]
+    async fn test_retry_transient_errors() {
+        // Act
+        let attempts = attempts(policy(false), "poll_get", transient_error).await;
+
+        // Assert
+        assert_eq!(attempts, 3);
+    }
+
+    #[tokio::test]
+    async fn test_do_not_retry_permanent_errors() {
+        // Act
+        let reject_attempts = attempts(policy(false), "poll_get", || {
+            CanisterClientError::CanisterError((RejectionCode::CanisterReject, "busy".to_string()))
+        })
+        .await;
+        let candid_attempts = attempts(policy(false), "poll_get", || {
+            candid::Error::msg("SysTransient").into()
+        })
+        .await;
+
+        // Assert
+        assert_eq!(reject_attempts, 1);
+        assert_eq!(candid_attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_do_not_retry_non_idempotent_methods_by_default() {
+        // Act
+        let default_attempts = attempts(policy(false), "poll_create", transient_error).await;
+        let opt_in_attempts = attempts(policy(true), "poll_create", transient_error).await;
+
+        // Assert
+        assert_eq!(default_attempts, 1);
+        assert_eq!(opt_in_attempts, 3);
+    }
+
+    #[test]
+    fn test_non_idempotent_methods_are_endpoints() {
+        for method in NON_IDEMPOTENT_METHODS {
+            assert!(
+                UPGRADER_API_METHODS.contains(method),
+                "{method} is not an endpoint of the UpgraderApi"
+            );
+        }
+    }
+}
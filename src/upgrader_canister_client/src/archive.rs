@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use candid::Principal;
+use ic_canister_client::{CanisterClient, CanisterClientResult};
+use upgrader_canister_did::error::Result;
+use upgrader_canister_did::ClosedPoll;
+
+/// An archive canister client.
+#[derive(Debug, Clone)]
+pub struct ArchiveCanisterClient<C>
+where
+    C: CanisterClient,
+{
+    /// The canister client.
+    client: C,
+}
+
+impl<C: CanisterClient> ArchiveCanisterClient<C> {
+    /// Create a new archive canister client.
+    ///
+    /// # Arguments
+    /// * `client` - The canister client.
+    pub fn new(client: C) -> Self {
+        Self { client }
+    }
+
+    /// Returns the upgrader canister allowed to append polls
+    pub async fn upgrader_get(&self) -> CanisterClientResult<Principal> {
+        self.client.query("upgrader_get", ()).await
+    }
+
+    /// Appends closed polls to the archive. Only the upgrader canister can call this method.
+    pub async fn polls_append(
+        &self,
+        polls: &[(u64, ClosedPoll)],
+    ) -> CanisterClientResult<Result<()>> {
+        self.client.update("polls_append", (polls,)).await
+    }
+
+    /// Returns an archived poll by id
+    pub async fn poll_get(&self, id: u64) -> CanisterClientResult<Option<ClosedPoll>> {
+        self.client.query("poll_get", (id,)).await
+    }
+
+    /// Returns the archived polls with id greater or equal than `start`
+    pub async fn poll_get_range(
+        &self,
+        start: u64,
+        limit: u64,
+    ) -> CanisterClientResult<BTreeMap<u64, ClosedPoll>> {
+        self.client.query("poll_get_range", (start, limit)).await
+    }
+
+    /// Returns the number of archived polls
+    pub async fn poll_count(&self) -> CanisterClientResult<u64> {
+        self.client.query("poll_count", ()).await
+    }
+}
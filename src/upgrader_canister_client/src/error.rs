@@ -0,0 +1,64 @@
+use ic_canister_client::{CanisterClientError, CanisterClientResult};
+use thiserror::Error;
+use upgrader_canister_did::UpgraderError;
+
+pub type UpgraderClientResult<T> = std::result::Result<T, UpgraderClientError>;
+
+/// The error of a call to the upgrader canister: either the call failed,
+/// or the canister executed it and returned an error
+#[derive(Debug, Error)]
+pub enum UpgraderClientError {
+    #[error("the canister call failed: {0}")]
+    Client(#[from] CanisterClientError),
+
+    #[error("the canister returned an error: {0}")]
+    Canister(#[from] UpgraderError),
+}
+
+impl UpgraderClientError {
+    /// Returns the error returned by the canister, if the call was executed
+    pub fn canister_error(&self) -> Option<&UpgraderError> {
+        match self {
+            Self::Client(_) => None,
+            Self::Canister(err) => Some(err),
+        }
+    }
+}
+
+/// Merges the result of a call and the result returned by the canister into a single result
+pub trait IntoUpgraderClientResult<T> {
+    fn into_client_result(self) -> UpgraderClientResult<T>;
+}
+
+impl<T> IntoUpgraderClientResult<T> for CanisterClientResult<Result<T, UpgraderError>> {
+    fn into_client_result(self) -> UpgraderClientResult<T> {
+        Ok(self??)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_into_client_result() {
+        let ok: CanisterClientResult<Result<u64, UpgraderError>> = Ok(Ok(1));
+        assert_eq!(ok.into_client_result().unwrap(), 1);
+
+        let canister_err: CanisterClientResult<Result<u64, UpgraderError>> =
+            Ok(Err(UpgraderError::NotAuthorized));
+        let err = canister_err.into_client_result().unwrap_err();
+        assert_eq!(err.canister_error(), Some(&UpgraderError::NotAuthorized));
+        assert_eq!(
+            err.to_string(),
+            "the canister returned an error: the user has no permission to call this method"
+        );
+
+        let client_err: CanisterClientResult<Result<u64, UpgraderError>> =
+            Err(candid::Error::msg("invalid candid").into());
+        let err = client_err.into_client_result().unwrap_err();
+        assert!(matches!(err, UpgraderClientError::Client(_)));
+        assert_eq!(err.canister_error(), None);
+    }
+}
@@ -4,10 +4,35 @@ use candid::Principal;
 use ic_canister_client::{CanisterClient, CanisterClientResult};
 use upgrader_canister_did::error::Result;
 use upgrader_canister_did::{
-    BuildData, ClosedPoll, PendingPoll, Permission, PermissionList, Poll, PollCreateData,
-    ProjectData,
+    AdminApprovalSettings, ArchiveSettings, AuditLogEntry, BuildData, ClosedPoll, HttpRequest,
+    HttpResponse, PendingAdminAction, PendingPoll, Permission, PermissionList, Poll,
+    PollCreateData, PollSettings, ProjectData, ProjectDeploymentStatus, ProjectHashStatus,
+    UnapprovedUpgradeAlert,
 };
 
+mod api;
+mod archive;
+mod error;
+#[cfg(feature = "mock")]
+mod mock;
+mod release;
+mod wait;
+
+#[cfg(feature = "tokio")]
+pub use api::RetryUpgrader;
+pub use api::{
+    is_transient_error, LoggingUpgrader, RetryPolicy, UpgraderApi, NON_IDEMPOTENT_METHODS,
+    UPGRADER_API_METHODS,
+};
+pub use archive::ArchiveCanisterClient;
+pub use error::{IntoUpgraderClientResult, UpgraderClientError, UpgraderClientResult};
+#[cfg(feature = "mock")]
+pub use mock::MockUpgrader;
+pub use release::{
+    wasm_file_hash, wasm_hash, ProjectHashRelease, ReleaseError, ReleaseMetadata, ReleasePoll,
+};
+pub use wait::WaitPollError;
+
 /// An upgrader canister client.
 #[derive(Debug, Clone)]
 pub struct UpgraderCanisterClient<C>
@@ -32,6 +57,11 @@ impl<C: CanisterClient> UpgraderCanisterClient<C> {
         self.client.query("canister_build_data", ()).await
     }
 
+    /// Sends a request to the read-only governance dashboard
+    pub async fn http_request(&self, request: &HttpRequest) -> CanisterClientResult<HttpResponse> {
+        self.client.query("http_request", (request,)).await
+    }
+
     /// Returns the permissions of a principal
     pub async fn admin_permissions_get(
         &self,
@@ -74,6 +104,98 @@ impl<C: CanisterClient> UpgraderCanisterClient<C> {
             .await
     }
 
+    /// Sets the multi-admin approval settings. Passing `None` disables the multi-admin approval.
+    pub async fn admin_approval_settings_set(
+        &self,
+        approval: Option<AdminApprovalSettings>,
+    ) -> CanisterClientResult<Result<()>> {
+        self.client
+            .update("admin_approval_settings_set", (approval,))
+            .await
+    }
+
+    /// Sets the archive settings. Passing `None` disables the archiving of the closed polls.
+    pub async fn admin_archive_settings_set(
+        &self,
+        archive: Option<ArchiveSettings>,
+    ) -> CanisterClientResult<Result<()>> {
+        self.client
+            .update("admin_archive_settings_set", (archive,))
+            .await
+    }
+
+    /// Sets the interval in seconds at which the poll timer finalizes the polls
+    pub async fn admin_poll_timer_interval_set(
+        &self,
+        interval_secs: u64,
+    ) -> CanisterClientResult<Result<()>> {
+        self.client
+            .update("admin_poll_timer_interval_set", (interval_secs,))
+            .await
+    }
+
+    /// Returns the interval in seconds at which the poll timer finalizes the polls
+    pub async fn poll_timer_interval_get(&self) -> CanisterClientResult<u64> {
+        self.client.query("poll_timer_interval_get", ()).await
+    }
+
+    /// Returns the settings applied to the polls
+    pub async fn poll_settings_get(&self) -> CanisterClientResult<PollSettings> {
+        self.client.query("poll_settings_get", ()).await
+    }
+
+    /// Returns the archive settings, if the archiving of the closed polls is enabled
+    pub async fn archive_settings_get(&self) -> CanisterClientResult<Option<ArchiveSettings>> {
+        self.client.query("archive_settings_get", ()).await
+    }
+
+    /// Returns the multi-admin approval settings, if the multi-admin approval is enabled
+    pub async fn admin_approval_settings_get(
+        &self,
+    ) -> CanisterClientResult<Option<AdminApprovalSettings>> {
+        self.client.query("admin_approval_settings_get", ()).await
+    }
+
+    /// Returns all the pending admin actions that are not expired
+    pub async fn admin_action_get_all_pending(
+        &self,
+    ) -> CanisterClientResult<Result<BTreeMap<u64, PendingAdminAction>>> {
+        self.client.query("admin_action_get_all_pending", ()).await
+    }
+
+    /// Approves a pending admin action. Returns true if the action was executed.
+    pub async fn admin_action_approve(&self, id: u64) -> CanisterClientResult<Result<bool>> {
+        self.client.update("admin_action_approve", (id,)).await
+    }
+
+    /// Returns the audit log entries starting from the `start` index
+    pub async fn audit_log_get(
+        &self,
+        start: u64,
+        limit: u64,
+    ) -> CanisterClientResult<Result<BTreeMap<u64, AuditLogEntry>>> {
+        self.client.query("audit_log_get", (start, limit)).await
+    }
+
+    /// Returns the number of entries in the audit log
+    pub async fn audit_log_len(&self) -> CanisterClientResult<Result<u64>> {
+        self.client.query("audit_log_len", ()).await
+    }
+
+    /// Returns up to `limit` alerts of unapproved upgrades starting from the `start` index
+    pub async fn alerts_get(
+        &self,
+        start: u64,
+        limit: u64,
+    ) -> CanisterClientResult<BTreeMap<u64, UnapprovedUpgradeAlert>> {
+        self.client.query("alerts_get", (start, limit)).await
+    }
+
+    /// Returns the number of recorded alerts of unapproved upgrades
+    pub async fn alerts_len(&self) -> CanisterClientResult<u64> {
+        self.client.query("alerts_len", ()).await
+    }
+
     /// Returns whether the inspect message is disabled.
     pub async fn is_inspect_message_disabled(&self) -> CanisterClientResult<bool> {
         self.client.query("is_inspect_message_disabled", ()).await
@@ -94,6 +216,47 @@ impl<C: CanisterClient> UpgraderCanisterClient<C> {
         self.client.query("project_get", (key,)).await
     }
 
+    /// Returns the status of a project hash, if it was ever approved
+    pub async fn project_hash_status(
+        &self,
+        project: &str,
+        hash: &str,
+    ) -> CanisterClientResult<Option<ProjectHashStatus>> {
+        self.client
+            .query("project_hash_status", (project, hash))
+            .await
+    }
+
+    /// Returns the status of all the approved and revoked hashes of a project
+    pub async fn project_hashes_get(
+        &self,
+        project: &str,
+    ) -> CanisterClientResult<BTreeMap<String, ProjectHashStatus>> {
+        self.client.query("project_hashes_get", (project,)).await
+    }
+
+    /// Returns the deployment status of the canisters of a project
+    pub async fn project_deployment_status(
+        &self,
+        key: &str,
+    ) -> CanisterClientResult<Result<ProjectDeploymentStatus>> {
+        self.client
+            .update("project_deployment_status", (key,))
+            .await
+    }
+
+    /// Revokes an approved project hash without waiting for a poll
+    pub async fn admin_project_hash_revoke(
+        &self,
+        project: &str,
+        hash: &str,
+        reason: &str,
+    ) -> CanisterClientResult<Result<()>> {
+        self.client
+            .update("admin_project_hash_revoke", (project, hash, reason))
+            .await
+    }
+
     /// Creates a new project
     pub async fn project_create(&self, project: &ProjectData) -> CanisterClientResult<Result<()>> {
         self.client.update("project_create", (project,)).await
@@ -114,6 +277,14 @@ impl<C: CanisterClient> UpgraderCanisterClient<C> {
         self.client.query("poll_get", (id,)).await
     }
 
+    /// Returns the id of the archive canister where the poll was moved, if it was archived
+    pub async fn poll_get_archive_canister(
+        &self,
+        id: u64,
+    ) -> CanisterClientResult<Option<Principal>> {
+        self.client.query("poll_get_archive_canister", (id,)).await
+    }
+
     /// Returns a poll by id searching in the pending polls
     pub async fn poll_get_pending(&self, id: u64) -> CanisterClientResult<Option<PendingPoll>> {
         self.client.query("poll_get_pending", (id,)).await
@@ -137,4 +308,16 @@ impl<C: CanisterClient> UpgraderCanisterClient<C> {
     ) -> CanisterClientResult<Result<()>> {
         self.client.update("poll_vote", (poll_id, approved)).await
     }
+
+    /// Finalizes a poll whose end timestamp is passed and returns the closed poll
+    pub async fn poll_finalize(&self, id: u64) -> CanisterClientResult<Result<ClosedPoll>> {
+        self.client.update("poll_finalize", (id,)).await
+    }
+
+    /// Finalizes all the polls whose end timestamp is passed and returns the closed polls
+    pub async fn polls_finalize_due(
+        &self,
+    ) -> CanisterClientResult<Result<BTreeMap<u64, ClosedPoll>>> {
+        self.client.update("polls_finalize_due", ()).await
+    }
 }
@@ -0,0 +1,886 @@
+use std::cell::Cell;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use candid::Principal;
+use ic_canister_client::CanisterClientResult;
+use upgrader_canister::endpoints;
+use upgrader_canister::state::UpgraderCanisterState;
+use upgrader_canister_did::error::Result;
+use upgrader_canister_did::{
+    AdminApprovalSettings, ArchiveSettings, AuditLogEntry, BuildData, CanisterDeploymentStatus,
+    ClosedPoll, DeploymentStatus, HttpRequest, HttpResponse, PendingAdminAction, PendingPoll,
+    Permission, PermissionList, Poll, PollCreateData, PollSettings, ProjectData,
+    ProjectDeploymentStatus, ProjectHashStatus, UnapprovedUpgradeAlert, UpgraderError, WasmHash,
+};
+
+/// An in-memory implementation of the upgrader canister API, meant for the tests of the
+/// canisters and scripts depending on the upgrader.
+///
+/// It runs the same endpoint logic as the canister without a replica, including the multi-admin
+/// approval and the audit log: the caller of the calls is set with `set_caller` and the time is
+/// controlled with `set_time_secs` and `advance_time`.
+/// The polls past their end time are finalized when the time advances, as the poll timers do.
+pub struct MockUpgrader {
+    state: UpgraderCanisterState,
+    caller: Cell<Principal>,
+    time_secs: Cell<u64>,
+}
+
+impl MockUpgrader {
+    /// Creates a mock with the given admin, who is also the initial caller
+    pub fn new(admin: Principal) -> Self {
+        let state = UpgraderCanisterState::default();
+        state
+            .permissions
+            .borrow_mut()
+            .add_permissions(admin, vec![Permission::Admin])
+            .expect("failed to add admin permission");
+
+        Self {
+            state,
+            caller: Cell::new(admin),
+            time_secs: Cell::new(0),
+        }
+    }
+
+    /// Sets the principal performing the following calls
+    pub fn set_caller(&self, caller: Principal) {
+        self.caller.set(caller);
+    }
+
+    /// Returns the principal performing the calls
+    pub fn caller(&self) -> Principal {
+        self.caller.get()
+    }
+
+    /// Returns the current timestamp in seconds
+    pub fn time_secs(&self) -> u64 {
+        self.time_secs.get()
+    }
+
+    /// Sets the current timestamp and finalizes the polls past their end time
+    pub fn set_time_secs(&self, timestamp_secs: u64) {
+        self.time_secs.set(timestamp_secs);
+        self.finalize_due_polls();
+    }
+
+    /// Advances the current time and finalizes the polls past their end time
+    pub fn advance_time(&self, duration: Duration) {
+        self.set_time_secs(self.time_secs().saturating_add(duration.as_secs()));
+    }
+
+    /// Returns the canister state, to inspect or prepare it directly
+    pub fn state(&self) -> &UpgraderCanisterState {
+        &self.state
+    }
+
+    fn finalize_due_polls(&self) -> BTreeMap<u64, ClosedPoll> {
+        endpoints::finalize_due_polls(&self.state, self.time_secs(), || true)
+    }
+
+    /// Returns the build data of the canister
+    pub async fn canister_build_data(&self) -> CanisterClientResult<BuildData> {
+        Ok(upgrader_canister::build_data::canister_build_data())
+    }
+
+    /// Sends a request to the read-only governance dashboard
+    pub async fn http_request(&self, request: &HttpRequest) -> CanisterClientResult<HttpResponse> {
+        Ok(upgrader_canister::http::http_request(&self.state, request))
+    }
+
+    /// Returns the permissions of a principal
+    pub async fn admin_permissions_get(
+        &self,
+        principal: Principal,
+    ) -> CanisterClientResult<Result<PermissionList>> {
+        Ok(endpoints::admin_permissions_get(
+            &self.state,
+            self.caller(),
+            principal,
+        ))
+    }
+
+    /// Adds permissions to a principal and returns the principal permissions
+    pub async fn admin_permissions_add(
+        &self,
+        principal: Principal,
+        permissions: &[Permission],
+    ) -> CanisterClientResult<Result<PermissionList>> {
+        Ok(endpoints::admin_permissions_add(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            principal,
+            permissions.to_vec(),
+        ))
+    }
+
+    /// Removes permissions from a principal and returns the principal permissions
+    pub async fn admin_permissions_remove(
+        &self,
+        principal: Principal,
+        permissions: &[Permission],
+    ) -> CanisterClientResult<Result<PermissionList>> {
+        Ok(endpoints::admin_permissions_remove(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            principal,
+            permissions.to_vec(),
+        ))
+    }
+
+    /// Disable/Enable the inspect message
+    pub async fn admin_disable_inspect_message(
+        &self,
+        value: bool,
+    ) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::admin_disable_inspect_message(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            value,
+        ))
+    }
+
+    /// Sets the multi-admin approval settings
+    pub async fn admin_approval_settings_set(
+        &self,
+        approval: Option<AdminApprovalSettings>,
+    ) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::admin_approval_settings_set(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            approval,
+        ))
+    }
+
+    /// Sets the archive settings. The closed polls are never moved to the archive canister.
+    pub async fn admin_archive_settings_set(
+        &self,
+        archive: Option<ArchiveSettings>,
+    ) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::admin_archive_settings_set(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            archive,
+        ))
+    }
+
+    /// Sets the interval in seconds at which the poll timer finalizes the polls.
+    /// The mock has no poll timer, the polls are finalized when the time advances.
+    pub async fn admin_poll_timer_interval_set(
+        &self,
+        interval_secs: u64,
+    ) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::admin_poll_timer_interval_set(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            interval_secs,
+        ))
+    }
+
+    /// Returns the interval in seconds at which the poll timer finalizes the polls
+    pub async fn poll_timer_interval_get(&self) -> CanisterClientResult<u64> {
+        Ok(self.state.settings.borrow().poll_timer_interval().as_secs())
+    }
+
+    /// Returns the archive settings
+    pub async fn archive_settings_get(&self) -> CanisterClientResult<Option<ArchiveSettings>> {
+        Ok(self.state.settings.borrow().archive())
+    }
+
+    /// Returns the multi-admin approval settings
+    pub async fn admin_approval_settings_get(
+        &self,
+    ) -> CanisterClientResult<Option<AdminApprovalSettings>> {
+        Ok(self.state.settings.borrow().admin_approval())
+    }
+
+    /// Returns all the pending admin actions that are not expired
+    pub async fn admin_action_get_all_pending(
+        &self,
+    ) -> CanisterClientResult<Result<BTreeMap<u64, PendingAdminAction>>> {
+        Ok(endpoints::admin_action_get_all_pending(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+        ))
+    }
+
+    /// Approves a pending admin action.
+    /// The action is executed as soon as the required number of distinct admins approved it.
+    /// Returns true if the action was executed.
+    pub async fn admin_action_approve(&self, id: u64) -> CanisterClientResult<Result<bool>> {
+        Ok(endpoints::admin_action_approve(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            id,
+        ))
+    }
+
+    /// Returns the audit log entries starting from the `start` index
+    pub async fn audit_log_get(
+        &self,
+        start: u64,
+        limit: u64,
+    ) -> CanisterClientResult<Result<BTreeMap<u64, AuditLogEntry>>> {
+        Ok(endpoints::audit_log_get(
+            &self.state,
+            self.caller(),
+            start,
+            limit,
+        ))
+    }
+
+    /// Returns the number of entries in the audit log
+    pub async fn audit_log_len(&self) -> CanisterClientResult<Result<u64>> {
+        Ok(endpoints::audit_log_len(&self.state, self.caller()))
+    }
+
+    /// Returns the alerts of unapproved upgrades.
+    /// The mock does not check the canisters of the projects, the alerts can be recorded
+    /// directly in the state.
+    pub async fn alerts_get(
+        &self,
+        start: u64,
+        limit: u64,
+    ) -> CanisterClientResult<BTreeMap<u64, UnapprovedUpgradeAlert>> {
+        Ok(self.state.alerts.borrow().page(start, limit))
+    }
+
+    /// Returns the number of recorded alerts of unapproved upgrades
+    pub async fn alerts_len(&self) -> CanisterClientResult<u64> {
+        Ok(self.state.alerts.borrow().len())
+    }
+
+    /// Returns whether the inspect message is disabled
+    pub async fn is_inspect_message_disabled(&self) -> CanisterClientResult<bool> {
+        Ok(self.state.settings.borrow().is_inspect_message_disabled())
+    }
+
+    /// Returns the permissions of the caller
+    pub async fn caller_permissions_get(&self) -> CanisterClientResult<Result<PermissionList>> {
+        Ok(Ok(self
+            .state
+            .permissions
+            .borrow()
+            .get_permissions(&self.caller())))
+    }
+
+    /// Returns the poll settings
+    pub async fn poll_settings_get(&self) -> CanisterClientResult<PollSettings> {
+        Ok(self.state.settings.borrow().poll_settings())
+    }
+
+    /// Returns all projects
+    pub async fn project_get_all(&self) -> CanisterClientResult<Vec<ProjectData>> {
+        Ok(self.state.projects.borrow().all())
+    }
+
+    /// Returns a project by key
+    pub async fn project_get(&self, key: &str) -> CanisterClientResult<Option<ProjectData>> {
+        Ok(self.state.projects.borrow().get(&key.to_string()))
+    }
+
+    /// Creates a new project
+    pub async fn project_create(&self, project: &ProjectData) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::project_create(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            project.clone(),
+        ))
+    }
+
+    /// Returns the status of a project hash, if it was ever approved
+    pub async fn project_hash_status(
+        &self,
+        project: &str,
+        hash: &str,
+    ) -> CanisterClientResult<Option<ProjectHashStatus>> {
+        Ok(WasmHash::normalize(hash).ok().and_then(|hash| {
+            self.state
+                .projects
+                .borrow()
+                .hash_status(&project.to_string(), &hash)
+        }))
+    }
+
+    /// Returns the status of all the approved and revoked hashes of a project
+    pub async fn project_hashes_get(
+        &self,
+        project: &str,
+    ) -> CanisterClientResult<BTreeMap<String, ProjectHashStatus>> {
+        Ok(self.state.projects.borrow().hashes(&project.to_string()))
+    }
+
+    /// Returns the deployment status of the canisters of a project.
+    /// The mock cannot read the installed modules, so the status of every canister is `Unknown`.
+    pub async fn project_deployment_status(
+        &self,
+        key: &str,
+    ) -> CanisterClientResult<Result<ProjectDeploymentStatus>> {
+        if let Err(err) = endpoints::project_deployment_status_inspect(
+            &self.state.permissions.borrow(),
+            &self.caller(),
+        ) {
+            return Ok(Err(err));
+        }
+
+        let key = key.to_string();
+        let projects = self.state.projects.borrow();
+        let Some(project) = projects.get(&key) else {
+            return Ok(Err(UpgraderError::ProjectNotFound(key)));
+        };
+
+        let canisters = project
+            .canisters
+            .unwrap_or_default()
+            .into_iter()
+            .map(|canister| CanisterDeploymentStatus {
+                canister,
+                module_hash: None,
+                status: DeploymentStatus::Unknown("not available in the mock".to_string()),
+            })
+            .collect();
+        Ok(Ok(ProjectDeploymentStatus {
+            latest_approved_hash: projects.latest_approved_hash(&key),
+            project: key,
+            canisters,
+        }))
+    }
+
+    /// Revokes an approved project hash without waiting for a poll
+    pub async fn admin_project_hash_revoke(
+        &self,
+        project: &str,
+        hash: &str,
+        reason: &str,
+    ) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::admin_project_hash_revoke(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            project.to_string(),
+            hash.to_string(),
+            reason.to_string(),
+        ))
+    }
+
+    /// Returns all pending polls
+    pub async fn poll_get_all_pending(&self) -> CanisterClientResult<BTreeMap<u64, PendingPoll>> {
+        Ok(self.state.polls.borrow().all_pending())
+    }
+
+    /// Returns all closed polls
+    pub async fn poll_get_all_closed(&self) -> CanisterClientResult<BTreeMap<u64, ClosedPoll>> {
+        Ok(self.state.polls.borrow().all_closed())
+    }
+
+    /// Returns a poll by id
+    pub async fn poll_get(&self, id: u64) -> CanisterClientResult<Option<Poll>> {
+        Ok(self.state.polls.borrow().get(&id))
+    }
+
+    /// Returns the id of the archive canister where the poll was moved, if it was archived
+    pub async fn poll_get_archive_canister(
+        &self,
+        id: u64,
+    ) -> CanisterClientResult<Option<Principal>> {
+        Ok(self.state.polls.borrow().get_archive_canister(&id))
+    }
+
+    /// Returns a poll by id searching in the pending polls
+    pub async fn poll_get_pending(&self, id: u64) -> CanisterClientResult<Option<PendingPoll>> {
+        Ok(self.state.polls.borrow().get_pending(&id))
+    }
+
+    /// Returns a poll by id searching in the closed polls
+    pub async fn poll_get_closed(&self, id: u64) -> CanisterClientResult<Option<ClosedPoll>> {
+        Ok(self.state.polls.borrow().get_closed(&id))
+    }
+
+    /// Creates a new poll and returns the generated poll id
+    pub async fn poll_create(&self, poll: &PollCreateData) -> CanisterClientResult<Result<u64>> {
+        Ok(endpoints::poll_create(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            poll.clone(),
+        ))
+    }
+
+    /// Votes for a poll. If the voter has already voted, the previous vote is replaced.
+    pub async fn poll_vote(
+        &self,
+        poll_id: u64,
+        approved: bool,
+    ) -> CanisterClientResult<Result<()>> {
+        Ok(endpoints::poll_vote(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            poll_id,
+            approved,
+        ))
+    }
+
+    /// Finalizes a poll whose end timestamp is passed and returns the closed poll
+    pub async fn poll_finalize(&self, id: u64) -> CanisterClientResult<Result<ClosedPoll>> {
+        Ok(endpoints::poll_finalize(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            id,
+        ))
+    }
+
+    /// Finalizes all the polls whose end timestamp is passed and returns the closed polls
+    pub async fn polls_finalize_due(
+        &self,
+    ) -> CanisterClientResult<Result<BTreeMap<u64, ClosedPoll>>> {
+        Ok(endpoints::polls_finalize_due(
+            &self.state,
+            self.caller(),
+            self.time_secs(),
+            || true,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use upgrader_canister_did::{AuditLogOutcome, PollAction, PollResult, PollType};
+
+    use super::*;
+
+    const ADMIN: Principal = Principal::from_slice(&[1; 29]);
+    const HASH: &str = "b1a2c3d4e5f60718293a4b5c6d7e8f90a1b2c3d4e5f60718293a4b5c6d7e8f90";
+
+    #[tokio::test]
+    async fn test_mock_project_hash_poll() {
+        // Arrange
+        let mock = MockUpgrader::new(ADMIN);
+        mock.set_time_secs(100);
+        mock.admin_permissions_add(
+            ADMIN,
+            &[
+                Permission::CreateProject,
+                Permission::CreatePoll,
+                Permission::VotePoll,
+            ],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        mock.project_create(&ProjectData {
+            key: "evm".to_string(),
+            name: "EVM".to_string(),
+            description: "EVM canister".to_string(),
+            canisters: None,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        let poll_id = mock
+            .poll_create(&PollCreateData {
+                description: "New release".to_string(),
+                poll_type: PollType::ProjectHash {
+                    project: "evm".to_string(),
+                    hash: HASH.to_uppercase(),
+                },
+                start_timestamp_secs: 100,
+                end_timestamp_secs: 200,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Act
+        mock.poll_vote(poll_id, true).await.unwrap().unwrap();
+        mock.advance_time(Duration::from_secs(101));
+
+        // Assert
+        let poll = mock.poll_get_closed(poll_id).await.unwrap().unwrap();
+        assert_eq!(poll.result, PollResult::Accepted);
+        assert_eq!(
+            mock.project_hash_status("evm", HASH).await.unwrap(),
+            Some(ProjectHashStatus::Approved { poll_id })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_revoked_hash_is_approved_only_by_a_reapprove_poll() {
+        // Arrange
+        let mock = MockUpgrader::new(ADMIN);
+        mock.admin_permissions_add(
+            ADMIN,
+            &[
+                Permission::CreateProject,
+                Permission::CreatePoll,
+                Permission::VotePoll,
+            ],
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        mock.project_create(&ProjectData {
+            key: "evm".to_string(),
+            name: "EVM".to_string(),
+            description: "EVM canister".to_string(),
+            canisters: None,
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        let poll = |poll_type: PollType| PollCreateData {
+            description: "Release".to_string(),
+            poll_type,
+            start_timestamp_secs: mock.time_secs(),
+            end_timestamp_secs: mock.time_secs() + 100,
+        };
+        let approve_hash = PollType::ProjectHash {
+            project: "evm".to_string(),
+            hash: HASH.to_string(),
+        };
+        let poll_id = mock
+            .poll_create(&poll(approve_hash.clone()))
+            .await
+            .unwrap()
+            .unwrap();
+        mock.poll_vote(poll_id, true).await.unwrap().unwrap();
+        mock.advance_time(Duration::from_secs(101));
+        mock.admin_project_hash_revoke("evm", HASH, "vulnerability")
+            .await
+            .unwrap()
+            .unwrap();
+
+        // Act
+        let approve_result = mock.poll_create(&poll(approve_hash)).await.unwrap();
+        let batch_result = mock
+            .poll_create(&poll(PollType::Batch(vec![PollAction::ProjectHash {
+                project: "evm".to_string(),
+                hash: HASH.to_string(),
+            }])))
+            .await
+            .unwrap();
+        let reapprove_poll_id = mock
+            .poll_create(&poll(PollType::ReapproveProjectHash {
+                project: "evm".to_string(),
+                hash: HASH.to_string(),
+            }))
+            .await
+            .unwrap()
+            .unwrap();
+        mock.poll_vote(reapprove_poll_id, true)
+            .await
+            .unwrap()
+            .unwrap();
+        mock.advance_time(Duration::from_secs(101));
+
+        // Assert
+        let revoked = Err(UpgraderError::ProjectHashRevoked {
+            project: "evm".to_string(),
+            hash: HASH.to_string(),
+        });
+        assert_eq!(approve_result, revoked);
+        assert_eq!(batch_result, revoked);
+        assert_eq!(
+            mock.project_hash_status("evm", HASH).await.unwrap(),
+            Some(ProjectHashStatus::Approved {
+                poll_id: reapprove_poll_id
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_permission_poll_rejects_the_anonymous_principal() {
+        // Arrange
+        let mock = MockUpgrader::new(ADMIN);
+        mock.admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+            .await
+            .unwrap()
+            .unwrap();
+        let poll = |poll_type: PollType| PollCreateData {
+            description: "Anonymous".to_string(),
+            poll_type,
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 100,
+        };
+
+        // Act
+        let add_result = mock
+            .poll_create(&poll(PollType::AddPermission {
+                principals: vec![Principal::anonymous()],
+                permissions: vec![Permission::VotePoll],
+            }))
+            .await
+            .unwrap();
+        let remove_result = mock
+            .poll_create(&poll(PollType::RemovePermission {
+                principals: vec![Principal::anonymous()],
+                permissions: vec![Permission::VotePoll],
+            }))
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(add_result, Err(UpgraderError::AnonymousPrincipalNotAllowed));
+        assert_eq!(
+            remove_result,
+            Err(UpgraderError::AnonymousPrincipalNotAllowed)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_pending_create_project_poll_reserves_the_key() {
+        // Arrange
+        let mock = MockUpgrader::new(ADMIN);
+        mock.admin_permissions_add(ADMIN, &[Permission::CreateProject, Permission::CreatePoll])
+            .await
+            .unwrap()
+            .unwrap();
+        let project = ProjectData {
+            key: "evm".to_string(),
+            name: "EVM".to_string(),
+            description: "EVM canister".to_string(),
+            canisters: None,
+        };
+        let create_poll = PollCreateData {
+            description: "New project".to_string(),
+            poll_type: PollType::CreateProject(project.clone()),
+            start_timestamp_secs: 0,
+            end_timestamp_secs: 100,
+        };
+        mock.poll_create(&create_poll).await.unwrap().unwrap();
+
+        // Act
+        let poll_result = mock.poll_create(&create_poll).await;
+        let create_result = mock.project_create(&project).await;
+
+        // Assert
+        assert_eq!(
+            poll_result.unwrap(),
+            Err(UpgraderError::NotUniqueKey("evm".to_string()))
+        );
+        assert_eq!(
+            create_result.unwrap(),
+            Err(UpgraderError::NotUniqueKey("evm".to_string()))
+        );
+        assert_eq!(mock.project_get("evm").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_checks_the_caller_permissions() {
+        // Arrange
+        let mock = MockUpgrader::new(ADMIN);
+        mock.set_caller(Principal::from_slice(&[2; 29]));
+
+        // Act
+        let permissions_result = mock
+            .admin_permissions_add(ADMIN, &[Permission::VotePoll])
+            .await;
+        let vote_result = mock.poll_vote(0, true).await;
+
+        // Assert
+        assert_eq!(
+            permissions_result.unwrap(),
+            Err(UpgraderError::MissingPermissions(vec![Permission::Admin]))
+        );
+        assert_eq!(
+            vote_result.unwrap(),
+            Err(UpgraderError::MissingPermissions(vec![
+                Permission::VotePoll
+            ]))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_polls_finalized_by_any_principal_are_recorded_in_the_audit_log() {
+        // Arrange
+        let stranger = Principal::from_slice(&[2; 29]);
+        let mock = MockUpgrader::new(ADMIN);
+        mock.admin_permissions_add(ADMIN, &[Permission::CreatePoll])
+            .await
+            .unwrap()
+            .unwrap();
+        let poll_id = mock
+            .poll_create(&PollCreateData {
+                description: "Grant".to_string(),
+                poll_type: PollType::AddPermission {
+                    principals: vec![stranger],
+                    permissions: vec![Permission::VotePoll],
+                },
+                start_timestamp_secs: 0,
+                end_timestamp_secs: 100,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        let entries_before = mock.audit_log_len().await.unwrap().unwrap();
+        mock.set_caller(stranger);
+
+        // Act
+        let early_finalize_result = mock.poll_finalize(poll_id).await.unwrap();
+        let early_finalize_due_result = mock.polls_finalize_due().await.unwrap();
+        // The time is moved without finalizing the ended polls as the timers would do
+        mock.time_secs.set(101);
+        let finalize_due_result = mock.polls_finalize_due().await.unwrap();
+
+        // Assert
+        assert!(early_finalize_result.is_err());
+        assert_eq!(early_finalize_due_result, Ok(BTreeMap::new()));
+        assert!(finalize_due_result.unwrap().contains_key(&poll_id));
+
+        mock.set_caller(ADMIN);
+        let audit_log = mock
+            .audit_log_get(entries_before, 10)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(audit_log.len(), 1);
+        assert_eq!(audit_log[&entries_before].caller, stranger);
+        assert_eq!(audit_log[&entries_before].method, "polls_finalize_due");
+        assert_eq!(audit_log[&entries_before].outcome, AuditLogOutcome::Success);
+    }
+
+    #[tokio::test]
+    async fn test_mock_admin_approval_threshold_is_not_greater_than_the_admins() {
+        // Arrange
+        let admin_2 = Principal::from_slice(&[2; 29]);
+        let mock = MockUpgrader::new(ADMIN);
+        let approval = |threshold| {
+            Some(AdminApprovalSettings {
+                threshold,
+                expiry_secs: 100,
+            })
+        };
+
+        // Act
+        let zero_threshold_result = mock.admin_approval_settings_set(approval(0)).await;
+        let high_threshold_result = mock.admin_approval_settings_set(approval(2)).await;
+        mock.admin_permissions_add(admin_2, &[Permission::Admin])
+            .await
+            .unwrap()
+            .unwrap();
+        let threshold_result = mock.admin_approval_settings_set(approval(2)).await;
+        let remove_admin_result = mock
+            .admin_permissions_remove(admin_2, &[Permission::Admin])
+            .await;
+
+        // Assert
+        assert!(zero_threshold_result.unwrap().is_err());
+        assert!(high_threshold_result.unwrap().is_err());
+        assert_eq!(threshold_result.unwrap(), Ok(()));
+        assert!(matches!(
+            remove_admin_result.unwrap(),
+            Err(UpgraderError::BadRequest(_))
+        ));
+        assert!(mock
+            .admin_action_get_all_pending()
+            .await
+            .unwrap()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_admin_action_failing_to_apply_stays_pending() {
+        // Arrange
+        let admin_2 = Principal::from_slice(&[2; 29]);
+        let mock = MockUpgrader::new(ADMIN);
+        mock.admin_permissions_add(admin_2, &[Permission::Admin])
+            .await
+            .unwrap()
+            .unwrap();
+        mock.admin_approval_settings_set(Some(AdminApprovalSettings {
+            threshold: 2,
+            expiry_secs: 100,
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+        let proposal_result = mock.admin_poll_timer_interval_set(0).await.unwrap();
+        let Err(UpgraderError::AdminApprovalRequired(action_id)) = proposal_result else {
+            panic!("expected a pending admin action, got {proposal_result:?}");
+        };
+
+        // Act
+        mock.set_caller(admin_2);
+        let approve_result = mock.admin_action_approve(action_id).await;
+
+        // Assert
+        assert!(approve_result.unwrap().is_err());
+        assert!(mock
+            .admin_action_get_all_pending()
+            .await
+            .unwrap()
+            .unwrap()
+            .contains_key(&action_id));
+    }
+
+    #[tokio::test]
+    async fn test_mock_admin_action_requires_the_approval_of_the_admins() {
+        // Arrange
+        let admin_2 = Principal::from_slice(&[2; 29]);
+        let voter = Principal::from_slice(&[3; 29]);
+        let mock = MockUpgrader::new(ADMIN);
+        mock.admin_permissions_add(admin_2, &[Permission::Admin])
+            .await
+            .unwrap()
+            .unwrap();
+        mock.admin_approval_settings_set(Some(AdminApprovalSettings {
+            threshold: 2,
+            expiry_secs: 100,
+        }))
+        .await
+        .unwrap()
+        .unwrap();
+
+        let proposal_result = mock
+            .admin_permissions_add(voter, &[Permission::VotePoll])
+            .await
+            .unwrap();
+        let Err(UpgraderError::AdminApprovalRequired(action_id)) = proposal_result else {
+            panic!("expected a pending admin action, got {proposal_result:?}");
+        };
+
+        // Act
+        mock.set_caller(admin_2);
+        let approve_result = mock.admin_action_approve(action_id).await;
+
+        // Assert
+        assert_eq!(approve_result.unwrap(), Ok(true));
+        assert!(mock
+            .admin_permissions_get(voter)
+            .await
+            .unwrap()
+            .unwrap()
+            .permissions
+            .contains(&Permission::VotePoll));
+        assert!(mock
+            .admin_action_get_all_pending()
+            .await
+            .unwrap()
+            .unwrap()
+            .is_empty());
+
+        let audit_log = mock.audit_log_get(0, 10).await.unwrap().unwrap();
+        assert_eq!(audit_log.len(), 4);
+        assert_eq!(
+            audit_log[&2].outcome,
+            AuditLogOutcome::Failure(UpgraderError::AdminApprovalRequired(action_id))
+        );
+        assert_eq!(audit_log[&3].caller, admin_2);
+        assert_eq!(audit_log[&3].method, "admin_action_approve");
+        assert_eq!(audit_log[&3].outcome, AuditLogOutcome::Success);
+    }
+}
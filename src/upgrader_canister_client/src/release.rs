@@ -0,0 +1,175 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use ic_canister_client::CanisterClient;
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+use upgrader_canister_did::{PollCreateData, PollType, WasmHash};
+
+use crate::{IntoUpgraderClientResult, UpgraderCanisterClient, UpgraderClientError};
+
+/// The errors of a release
+#[derive(Debug, Error)]
+pub enum ReleaseError {
+    #[error("failed to access {path}: {source}")]
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    #[error("failed to read the git metadata: {0}")]
+    Git(String),
+
+    #[error("the poll creation failed: {0}")]
+    Upgrader(#[from] UpgraderClientError),
+}
+
+/// The git metadata of a release, the same reported by the canister build data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseMetadata {
+    pub pkg_version: String,
+    pub git_branch: String,
+    pub git_sha: String,
+    pub git_commit_timestamp: String,
+}
+
+impl ReleaseMetadata {
+    /// Reads the git metadata of the HEAD commit of the repository in the given directory
+    pub fn from_git(repo_dir: &Path, pkg_version: &str) -> Result<Self, ReleaseError> {
+        Ok(Self {
+            pkg_version: pkg_version.to_string(),
+            git_branch: git(repo_dir, &["rev-parse", "--abbrev-ref", "HEAD"])?,
+            git_sha: git(repo_dir, &["rev-parse", "HEAD"])?,
+            git_commit_timestamp: git(repo_dir, &["log", "-1", "--format=%cI"])?,
+        })
+    }
+
+    /// Returns the description of the poll approving the release of the project
+    pub fn poll_description(&self, project: &str) -> String {
+        format!(
+            "Release {} of {project} from commit {} on branch {} committed at {}",
+            self.pkg_version, self.git_sha, self.git_branch, self.git_commit_timestamp
+        )
+    }
+}
+
+/// Runs a git command and returns its trimmed output
+fn git(repo_dir: &Path, args: &[&str]) -> Result<String, ReleaseError> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(repo_dir)
+        .output()
+        .map_err(|source| ReleaseError::Io {
+            path: repo_dir.to_path_buf(),
+            source,
+        })?;
+
+    if !output.status.success() {
+        return Err(ReleaseError::Git(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A release of a project wasm to be approved by a `ProjectHash` poll
+#[derive(Debug, Clone)]
+pub struct ProjectHashRelease {
+    /// The key of the project
+    pub project: String,
+    /// The path of the wasm file, as it is installed in the canisters
+    pub wasm_path: PathBuf,
+    /// The git metadata used to generate the poll description
+    pub metadata: ReleaseMetadata,
+    /// The local directory where the wasm file is copied as a release artifact,
+    /// named after its hash. Publishing the artifact is left to the release pipeline.
+    pub artifact_dir: Option<PathBuf>,
+    /// The timestamp when the poll opens
+    pub start_timestamp_secs: u64,
+    /// The timestamp when the poll closes
+    pub end_timestamp_secs: u64,
+}
+
+/// The poll created for a release
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReleasePoll {
+    pub poll_id: u64,
+    pub hash: WasmHash,
+}
+
+/// Returns the hash of a wasm module, in the format returned by the management canister
+pub fn wasm_hash(wasm: &[u8]) -> WasmHash {
+    WasmHash(Sha256::digest(wasm).into())
+}
+
+/// Reads a wasm file and returns its hash
+pub fn wasm_file_hash(path: &Path) -> Result<WasmHash, ReleaseError> {
+    let wasm = std::fs::read(path).map_err(|source| ReleaseError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(wasm_hash(&wasm))
+}
+
+impl<C: CanisterClient> UpgraderCanisterClient<C> {
+    /// Computes the hash of the release wasm, copies the wasm to the artifact directory if set,
+    /// and creates a `ProjectHash` poll to approve it
+    pub async fn release_project_hash(
+        &self,
+        release: &ProjectHashRelease,
+    ) -> Result<ReleasePoll, ReleaseError> {
+        let hash = wasm_file_hash(&release.wasm_path)?;
+
+        if let Some(artifact_dir) = &release.artifact_dir {
+            let destination = artifact_dir.join(format!("{hash}.wasm"));
+            std::fs::create_dir_all(artifact_dir)
+                .and_then(|_| std::fs::copy(&release.wasm_path, &destination))
+                .map_err(|source| ReleaseError::Io {
+                    path: destination,
+                    source,
+                })?;
+        }
+
+        let poll = PollCreateData {
+            description: release.metadata.poll_description(&release.project),
+            poll_type: PollType::ProjectHash {
+                project: release.project.clone(),
+                hash: hash.to_string(),
+            },
+            start_timestamp_secs: release.start_timestamp_secs,
+            end_timestamp_secs: release.end_timestamp_secs,
+        };
+        let poll_id = self.poll_create(&poll).await.into_client_result()?;
+
+        Ok(ReleasePoll { poll_id, hash })
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_wasm_hash() {
+        assert_eq!(
+            wasm_hash(b"").to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+
+    #[test]
+    fn test_poll_description() {
+        let metadata = ReleaseMetadata {
+            pkg_version: "1.2.0".to_string(),
+            git_branch: "main".to_string(),
+            git_sha: "acf6c5744b1f4f29c5960a25f4fb4056e2ceedc3".to_string(),
+            git_commit_timestamp: "2024-01-01T00:00:00Z".to_string(),
+        };
+
+        assert_eq!(
+            metadata.poll_description("evm"),
+            "Release 1.2.0 of evm from commit acf6c5744b1f4f29c5960a25f4fb4056e2ceedc3 on branch main committed at 2024-01-01T00:00:00Z"
+        );
+    }
+}
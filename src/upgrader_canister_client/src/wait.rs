@@ -0,0 +1,89 @@
+use std::future::Future;
+use std::time::Duration;
+
+use candid::Principal;
+use ic_canister_client::{CanisterClient, CanisterClientError};
+use thiserror::Error;
+use upgrader_canister_did::{ClosedPoll, Poll};
+
+use crate::UpgraderCanisterClient;
+
+/// The first delay between two checks of the poll status
+const WAIT_INITIAL_DELAY: Duration = Duration::from_millis(100);
+
+/// The maximum delay between two checks of the poll status
+const WAIT_MAX_DELAY: Duration = Duration::from_secs(10);
+
+/// The errors returned while waiting for the result of a poll
+#[derive(Debug, Error)]
+pub enum WaitPollError {
+    #[error("the canister call failed: {0}")]
+    Client(#[from] CanisterClientError),
+
+    #[error("the poll {0} does not exist")]
+    NotFound(u64),
+
+    #[error("the poll {poll_id} was moved to the archive canister {archive_canister}")]
+    Archived {
+        poll_id: u64,
+        archive_canister: Principal,
+    },
+
+    #[error("the poll {0} was not closed before the timeout")]
+    Timeout(u64),
+}
+
+impl<C: CanisterClient> UpgraderCanisterClient<C> {
+    /// Waits until the poll is closed and returns it.
+    /// The poll is checked with an exponential backoff until the timeout elapses.
+    /// It sleeps with the tokio timer, so it requires the `tokio` feature.
+    #[cfg(feature = "tokio")]
+    pub async fn wait_for_poll_result(
+        &self,
+        id: u64,
+        timeout: Duration,
+    ) -> Result<ClosedPoll, WaitPollError> {
+        self.wait_for_poll_result_with(id, timeout, tokio::time::sleep)
+            .await
+    }
+
+    /// Waits until the poll is closed and returns it, using `sleep` to wait between two checks.
+    /// The timeout is measured as the total time passed to `sleep`, so that a test environment
+    /// can advance its own clock instead of sleeping, e.g. pocket-ic `advance_time`.
+    pub async fn wait_for_poll_result_with<F, Fut>(
+        &self,
+        id: u64,
+        timeout: Duration,
+        mut sleep: F,
+    ) -> Result<ClosedPoll, WaitPollError>
+    where
+        F: FnMut(Duration) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let mut waited = Duration::ZERO;
+        let mut delay = WAIT_INITIAL_DELAY;
+
+        loop {
+            match self.poll_get(id).await? {
+                Some(Poll::Closed(poll)) => return Ok(poll),
+                Some(Poll::Archived { archive_canister }) => {
+                    return Err(WaitPollError::Archived {
+                        poll_id: id,
+                        archive_canister,
+                    })
+                }
+                Some(Poll::Pending(_)) => {}
+                None => return Err(WaitPollError::NotFound(id)),
+            }
+
+            if waited >= timeout {
+                return Err(WaitPollError::Timeout(id));
+            }
+
+            let next_delay = delay.min(timeout - waited);
+            sleep(next_delay).await;
+            waited += next_delay;
+            delay = (delay * 2).min(WAIT_MAX_DELAY);
+        }
+    }
+}
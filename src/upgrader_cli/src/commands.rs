@@ -0,0 +1,616 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use candid::Principal;
+use clap::{Args, Subcommand, ValueEnum};
+use ic_canister_client::CanisterClient;
+use serde::Serialize;
+use upgrader_canister_client::{ProjectHashRelease, ReleaseMetadata, UpgraderCanisterClient};
+use upgrader_canister_did::{
+    ClosedPoll, PendingPoll, Permission, PermissionList, PollAction, PollCreateData, PollType,
+    ProjectCanister, ProjectData, SettingsPatch,
+};
+
+use crate::output::{print, print_with, OutputFormat};
+use crate::parse_principal;
+
+/// The default duration of a poll, one week
+const DEFAULT_POLL_DURATION_SECS: u64 = 7 * 24 * 3600;
+
+/// The network of a project canister when it is not set
+const DEFAULT_CANISTER_NETWORK: &str = "ic";
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectCommand {
+    /// Lists all the projects
+    List,
+    /// Shows a project
+    Show { key: String },
+    /// Creates a new project
+    Create(ProjectArgs),
+}
+
+/// The data of a project
+#[derive(Debug, Args)]
+pub struct ProjectArgs {
+    #[arg(long)]
+    key: String,
+    #[arg(long)]
+    name: String,
+    #[arg(long)]
+    description: String,
+    /// A canister governed by the project, as `<canister id>[@<network>]`;
+    /// the network is `ic` if not set
+    #[arg(long = "canister", value_parser = parse_project_canister)]
+    canisters: Vec<ProjectCanister>,
+}
+
+impl From<ProjectArgs> for ProjectData {
+    fn from(value: ProjectArgs) -> Self {
+        ProjectData {
+            key: value.key,
+            name: value.name,
+            description: value.description,
+            canisters: (!value.canisters.is_empty()).then_some(value.canisters),
+        }
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PollCommand {
+    /// Lists the pending and closed polls
+    List {
+        /// Lists only the pending polls
+        #[arg(long)]
+        pending: bool,
+    },
+    /// Shows a poll
+    Show { id: u64 },
+    /// Creates a new poll
+    Create {
+        #[command(flatten)]
+        timing: PollTiming,
+        #[command(subcommand)]
+        poll_type: PollTypeCommand,
+    },
+    /// Votes for a poll, the vote approves the poll unless `--reject` is set
+    Vote {
+        id: u64,
+        #[arg(long)]
+        reject: bool,
+    },
+}
+
+/// The description and the voting period of a new poll
+#[derive(Debug, Args)]
+pub struct PollTiming {
+    /// The description of the poll
+    #[arg(long)]
+    description: String,
+    /// The timestamp in seconds when the poll opens, now by default
+    #[arg(long)]
+    start_timestamp_secs: Option<u64>,
+    /// The duration of the poll in seconds
+    #[arg(long, default_value_t = DEFAULT_POLL_DURATION_SECS)]
+    duration_secs: u64,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PollTypeCommand {
+    /// A poll to approve a project hash
+    ProjectHash {
+        #[arg(long)]
+        project: String,
+        #[arg(long)]
+        hash: String,
+    },
+    /// A poll to revoke an approved project hash
+    RevokeProjectHash {
+        #[arg(long)]
+        project: String,
+        #[arg(long)]
+        hash: String,
+        #[arg(long)]
+        reason: String,
+    },
+    /// A poll to approve again a revoked project hash
+    ReapproveProjectHash {
+        #[arg(long)]
+        project: String,
+        #[arg(long)]
+        hash: String,
+    },
+    /// A poll to add permissions to principals
+    AddPermission(PermissionsChange),
+    /// A poll to remove permissions from principals
+    RemovePermission(PermissionsChange),
+    /// A poll to update the canister settings, the settings not set are unchanged
+    UpdateSettings(SettingsChange),
+    /// A poll to create a new project
+    CreateProject(ProjectArgs),
+    /// A poll to replace the data of an existing project, including its canisters
+    UpdateProject(ProjectArgs),
+    /// A poll to execute multiple actions atomically
+    Batch {
+        /// An action of the batch as JSON, e.g. `{"ProjectHash":{"project":"key","hash":"..."}}`;
+        /// the actions are executed in the order they are given
+        #[arg(long = "action", required = true, value_parser = parse_poll_action)]
+        actions: Vec<PollAction>,
+    },
+}
+
+/// The settings changed by an `UpdateSettings` poll
+#[derive(Debug, Args)]
+pub struct SettingsChange {
+    /// Disables or enables the inspect message
+    #[arg(long)]
+    disable_inspect_message: Option<bool>,
+    /// The minimum number of votes of a poll
+    #[arg(long)]
+    poll_quorum: Option<u64>,
+    /// The interval in seconds at which the poll timer finalizes the polls
+    #[arg(long)]
+    poll_timer_interval_secs: Option<u64>,
+    /// The minimum duration of a poll in seconds
+    #[arg(long, conflicts_with = "clear_poll_min_duration")]
+    poll_min_duration_secs: Option<u64>,
+    /// Clears the minimum duration of a poll
+    #[arg(long)]
+    clear_poll_min_duration: bool,
+    /// The maximum duration of a poll in seconds
+    #[arg(long, conflicts_with = "clear_poll_max_duration")]
+    poll_max_duration_secs: Option<u64>,
+    /// Clears the maximum duration of a poll
+    #[arg(long)]
+    clear_poll_max_duration: bool,
+}
+
+impl From<SettingsChange> for SettingsPatch {
+    fn from(value: SettingsChange) -> Self {
+        let duration =
+            |secs: Option<u64>, clear: bool| if clear { Some(None) } else { secs.map(Some) };
+        SettingsPatch {
+            disable_inspect_message: value.disable_inspect_message,
+            poll_quorum: value.poll_quorum,
+            poll_timer_interval_secs: value.poll_timer_interval_secs,
+            poll_min_duration_secs: duration(
+                value.poll_min_duration_secs,
+                value.clear_poll_min_duration,
+            ),
+            poll_max_duration_secs: duration(
+                value.poll_max_duration_secs,
+                value.clear_poll_max_duration,
+            ),
+        }
+    }
+}
+
+/// The permissions changed for a set of principals
+#[derive(Debug, Args)]
+pub struct PermissionsChange {
+    #[arg(long = "principal", required = true, value_parser = parse_principal)]
+    principals: Vec<Principal>,
+    #[arg(long = "permission", required = true, value_enum)]
+    permissions: Vec<PermissionArg>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum PermissionCommand {
+    /// Shows the permissions of the caller
+    Caller,
+    /// Shows the permissions of a principal
+    Get {
+        #[arg(value_parser = parse_principal)]
+        principal: Principal,
+    },
+    /// Adds permissions to a principal
+    Add {
+        #[arg(value_parser = parse_principal)]
+        principal: Principal,
+        #[arg(required = true, value_enum)]
+        permissions: Vec<PermissionArg>,
+    },
+    /// Removes permissions from a principal
+    Remove {
+        #[arg(value_parser = parse_principal)]
+        principal: Principal,
+        #[arg(required = true, value_enum)]
+        permissions: Vec<PermissionArg>,
+    },
+}
+
+/// The arguments of the `release` command
+#[derive(Debug, Args)]
+pub struct ReleaseArgs {
+    /// The key of the project
+    #[arg(long)]
+    project: String,
+    /// The path of the wasm file, as it is installed in the canisters
+    #[arg(long)]
+    wasm: PathBuf,
+    /// The version of the release
+    #[arg(long)]
+    release_version: String,
+    /// The git repository the release is built from
+    #[arg(long, default_value = ".")]
+    repo_dir: PathBuf,
+    /// The local directory where the wasm file is copied as a release artifact, named after its hash
+    #[arg(long)]
+    artifact_dir: Option<PathBuf>,
+    /// The timestamp in seconds when the poll opens, now by default
+    #[arg(long)]
+    start_timestamp_secs: Option<u64>,
+    /// The duration of the poll in seconds
+    #[arg(long, default_value_t = DEFAULT_POLL_DURATION_SECS)]
+    duration_secs: u64,
+}
+
+/// A permission as a command-line argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PermissionArg {
+    Admin,
+    CreateProject,
+    CreatePoll,
+    VotePoll,
+}
+
+impl From<PermissionArg> for Permission {
+    fn from(value: PermissionArg) -> Self {
+        match value {
+            PermissionArg::Admin => Permission::Admin,
+            PermissionArg::CreateProject => Permission::CreateProject,
+            PermissionArg::CreatePoll => Permission::CreatePoll,
+            PermissionArg::VotePoll => Permission::VotePoll,
+        }
+    }
+}
+
+/// The pending and closed polls listed by the `poll list` command
+#[derive(Debug, Serialize)]
+struct PollListing {
+    pending: Vec<(u64, PendingPoll)>,
+    closed: Vec<(u64, ClosedPoll)>,
+}
+
+/// The poll created by the `release` command
+#[derive(Debug, Serialize)]
+struct ReleaseOutput {
+    poll_id: u64,
+    hash: String,
+}
+
+/// Executes a command against the upgrader canister and prints its result
+pub async fn execute<C: CanisterClient>(
+    client: &UpgraderCanisterClient<C>,
+    command: crate::Command,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    match command {
+        crate::Command::BuildData => print(format, &client.canister_build_data().await?),
+        crate::Command::Project(command) => execute_project(client, command, format).await,
+        crate::Command::Poll(command) => execute_poll(client, command, format).await,
+        crate::Command::Permission(command) => execute_permission(client, command, format).await,
+        crate::Command::Release(args) => execute_release(client, args, format).await,
+    }
+}
+
+async fn execute_project<C: CanisterClient>(
+    client: &UpgraderCanisterClient<C>,
+    command: ProjectCommand,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    match command {
+        ProjectCommand::List => {
+            let projects = client.project_get_all().await?;
+            print_with(format, &projects, |projects| {
+                projects
+                    .iter()
+                    .map(|project| format!("{}\t{}", project.key, project.name))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+        }
+        ProjectCommand::Show { key } => print(format, &client.project_get(&key).await?),
+        ProjectCommand::Create(args) => {
+            let project = ProjectData::from(args);
+            client.project_create(&project).await??;
+            print(format, &project)
+        }
+    }
+}
+
+async fn execute_poll<C: CanisterClient>(
+    client: &UpgraderCanisterClient<C>,
+    command: PollCommand,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    match command {
+        PollCommand::List { pending } => {
+            let listing = PollListing {
+                pending: client.poll_get_all_pending().await?.into_iter().collect(),
+                closed: if pending {
+                    vec![]
+                } else {
+                    client.poll_get_all_closed().await?.into_iter().collect()
+                },
+            };
+            print_with(format, &listing, |listing| {
+                let pending = listing
+                    .pending
+                    .iter()
+                    .map(|(id, poll)| format!("{id}\tpending\t{}", poll.description));
+                let closed = listing
+                    .closed
+                    .iter()
+                    .map(|(id, poll)| format!("{id}\t{:?}\t{}", poll.result, poll.description));
+                pending.chain(closed).collect::<Vec<_>>().join("\n")
+            })
+        }
+        PollCommand::Show { id } => print(format, &client.poll_get(id).await?),
+        PollCommand::Create { timing, poll_type } => {
+            let poll = poll_create_data(timing, poll_type, now_secs()?);
+            let id = client.poll_create(&poll).await??;
+            print_with(format, &id, |id| format!("Created poll {id}"))
+        }
+        PollCommand::Vote { id, reject } => {
+            client.poll_vote(id, !reject).await??;
+            let vote = if reject { "rejected" } else { "approved" };
+            print_with(format, &vote, |vote| format!("Poll {id} {vote}"))
+        }
+    }
+}
+
+async fn execute_permission<C: CanisterClient>(
+    client: &UpgraderCanisterClient<C>,
+    command: PermissionCommand,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let permissions = match command {
+        PermissionCommand::Caller => client.caller_permissions_get().await??,
+        PermissionCommand::Get { principal } => client.admin_permissions_get(principal).await??,
+        PermissionCommand::Add {
+            principal,
+            permissions,
+        } => {
+            client
+                .admin_permissions_add(principal, &to_permissions(permissions))
+                .await??
+        }
+        PermissionCommand::Remove {
+            principal,
+            permissions,
+        } => {
+            client
+                .admin_permissions_remove(principal, &to_permissions(permissions))
+                .await??
+        }
+    };
+    print_with(format, &permissions, |permissions: &PermissionList| {
+        permissions
+            .permissions
+            .iter()
+            .map(|permission| format!("{permission:?}"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    })
+}
+
+async fn execute_release<C: CanisterClient>(
+    client: &UpgraderCanisterClient<C>,
+    args: ReleaseArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let start_timestamp_secs = args.start_timestamp_secs.unwrap_or(now_secs()?);
+    let release = ProjectHashRelease {
+        metadata: ReleaseMetadata::from_git(&args.repo_dir, &args.release_version)?,
+        project: args.project,
+        wasm_path: args.wasm,
+        artifact_dir: args.artifact_dir,
+        start_timestamp_secs,
+        end_timestamp_secs: start_timestamp_secs.saturating_add(args.duration_secs),
+    };
+
+    let poll = client.release_project_hash(&release).await?;
+    let output = ReleaseOutput {
+        poll_id: poll.poll_id,
+        hash: poll.hash.to_string(),
+    };
+    print_with(format, &output, |output| {
+        format!(
+            "Created poll {} to approve the hash {}",
+            output.poll_id, output.hash
+        )
+    })
+}
+
+/// Builds the data of a new poll
+fn poll_create_data(
+    timing: PollTiming,
+    poll_type: PollTypeCommand,
+    now_secs: u64,
+) -> PollCreateData {
+    let start_timestamp_secs = timing.start_timestamp_secs.unwrap_or(now_secs);
+    let poll_type = match poll_type {
+        PollTypeCommand::ProjectHash { project, hash } => PollType::ProjectHash { project, hash },
+        PollTypeCommand::RevokeProjectHash {
+            project,
+            hash,
+            reason,
+        } => PollType::RevokeProjectHash {
+            project,
+            hash,
+            reason,
+        },
+        PollTypeCommand::ReapproveProjectHash { project, hash } => {
+            PollType::ReapproveProjectHash { project, hash }
+        }
+        PollTypeCommand::AddPermission(change) => PollType::AddPermission {
+            principals: change.principals,
+            permissions: to_permissions(change.permissions),
+        },
+        PollTypeCommand::RemovePermission(change) => PollType::RemovePermission {
+            principals: change.principals,
+            permissions: to_permissions(change.permissions),
+        },
+        PollTypeCommand::UpdateSettings(change) => PollType::UpdateSettings(change.into()),
+        PollTypeCommand::CreateProject(project) => PollType::CreateProject(project.into()),
+        PollTypeCommand::UpdateProject(project) => PollType::UpdateProject(project.into()),
+        PollTypeCommand::Batch { actions } => PollType::Batch(actions),
+    };
+
+    PollCreateData {
+        description: timing.description,
+        poll_type,
+        start_timestamp_secs,
+        end_timestamp_secs: start_timestamp_secs.saturating_add(timing.duration_secs),
+    }
+}
+
+/// Parses a project canister from `<canister id>[@<network>]`
+fn parse_project_canister(text: &str) -> Result<ProjectCanister, String> {
+    let (canister_id, network) = text
+        .split_once('@')
+        .unwrap_or((text, DEFAULT_CANISTER_NETWORK));
+    if network.is_empty() {
+        return Err(format!("missing network in [{text}]"));
+    }
+    Ok(ProjectCanister {
+        network: network.to_string(),
+        canister_id: parse_principal(canister_id)?,
+    })
+}
+
+/// Parses a batch poll action from its JSON representation
+fn parse_poll_action(text: &str) -> Result<PollAction, String> {
+    serde_json::from_str(text).map_err(|err| format!("invalid poll action [{text}]: {err}"))
+}
+
+fn to_permissions(permissions: Vec<PermissionArg>) -> Vec<Permission> {
+    permissions.into_iter().map(Permission::from).collect()
+}
+
+/// Returns the current timestamp in seconds
+fn now_secs() -> anyhow::Result<u64> {
+    Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs())
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_poll_create_data() {
+        // Arrange
+        let timing = PollTiming {
+            description: "Add voters".to_string(),
+            start_timestamp_secs: None,
+            duration_secs: 100,
+        };
+        let poll_type = PollTypeCommand::AddPermission(PermissionsChange {
+            principals: vec![Principal::anonymous()],
+            permissions: vec![PermissionArg::VotePoll],
+        });
+
+        // Act
+        let poll = poll_create_data(timing, poll_type, 1_000);
+
+        // Assert
+        assert_eq!(poll.description, "Add voters");
+        assert_eq!(poll.start_timestamp_secs, 1_000);
+        assert_eq!(poll.end_timestamp_secs, 1_100);
+        assert_eq!(
+            poll.poll_type,
+            PollType::AddPermission {
+                principals: vec![Principal::anonymous()],
+                permissions: vec![Permission::VotePoll],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_project_canister() {
+        // Arrange
+        let canister_id = Principal::from_slice(&[1; 10]);
+
+        // Act
+        let default_network = parse_project_canister(&canister_id.to_text());
+        let network = parse_project_canister(&format!("{canister_id}@testnet"));
+        let missing_network = parse_project_canister(&format!("{canister_id}@"));
+        let invalid_id = parse_project_canister("invalid@ic");
+
+        // Assert
+        assert_eq!(
+            default_network,
+            Ok(ProjectCanister {
+                network: "ic".to_string(),
+                canister_id
+            })
+        );
+        assert_eq!(
+            network,
+            Ok(ProjectCanister {
+                network: "testnet".to_string(),
+                canister_id
+            })
+        );
+        assert!(missing_network.is_err());
+        assert!(invalid_id.is_err());
+    }
+
+    #[test]
+    fn test_poll_create_data_for_batch_and_settings() {
+        // Arrange
+        let timing = || PollTiming {
+            description: "Release".to_string(),
+            start_timestamp_secs: Some(10),
+            duration_secs: 100,
+        };
+        let batch = PollTypeCommand::Batch {
+            actions: vec![
+                parse_poll_action(
+                    r#"{"AddPermission":{"principals":["aaaaa-aa"],"permissions":["VotePoll"]}}"#,
+                )
+                .unwrap(),
+                parse_poll_action(r#"{"ProjectHash":{"project":"key","hash":"hash"}}"#).unwrap(),
+            ],
+        };
+        let settings = PollTypeCommand::UpdateSettings(SettingsChange {
+            disable_inspect_message: None,
+            poll_quorum: Some(3),
+            poll_timer_interval_secs: None,
+            poll_min_duration_secs: Some(60),
+            clear_poll_min_duration: false,
+            poll_max_duration_secs: None,
+            clear_poll_max_duration: true,
+        });
+
+        // Act
+        let batch_poll = poll_create_data(timing(), batch, 1_000);
+        let settings_poll = poll_create_data(timing(), settings, 1_000);
+
+        // Assert
+        assert_eq!(
+            batch_poll.poll_type,
+            PollType::Batch(vec![
+                PollAction::AddPermission {
+                    principals: vec![Principal::management_canister()],
+                    permissions: vec![Permission::VotePoll],
+                },
+                PollAction::ProjectHash {
+                    project: "key".to_string(),
+                    hash: "hash".to_string(),
+                },
+            ])
+        );
+        assert_eq!(
+            settings_poll.poll_type,
+            PollType::UpdateSettings(SettingsPatch {
+                poll_quorum: Some(3),
+                poll_min_duration_secs: Some(Some(60)),
+                poll_max_duration_secs: Some(None),
+                ..Default::default()
+            })
+        );
+    }
+}
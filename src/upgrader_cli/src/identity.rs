@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context};
+
+/// Returns the path of the PEM file of a dfx identity.
+/// Only the identities stored as plaintext PEM files are supported.
+pub fn dfx_identity_pem(name: &str) -> anyhow::Result<PathBuf> {
+    let home = std::env::var_os("HOME").context("the HOME environment variable is not set")?;
+    let path = identity_pem_path(Path::new(&home), name);
+    if !path.is_file() {
+        bail!(
+            "the PEM file of the dfx identity [{name}] was not found at {}, use --identity-pem instead",
+            path.display()
+        );
+    }
+    Ok(path)
+}
+
+/// Returns the path where dfx stores the PEM file of an identity
+fn identity_pem_path(home: &Path, name: &str) -> PathBuf {
+    home.join(".config")
+        .join("dfx")
+        .join("identity")
+        .join(name)
+        .join("identity.pem")
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_identity_pem_path() {
+        assert_eq!(
+            identity_pem_path(Path::new("/home/user"), "default"),
+            PathBuf::from("/home/user/.config/dfx/identity/default/identity.pem")
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use candid::Principal;
+use clap::{Parser, Subcommand};
+use ic_canister_client::IcAgentClient;
+use upgrader_canister_client::UpgraderCanisterClient;
+
+mod commands;
+mod identity;
+mod output;
+
+use commands::{PermissionCommand, PollCommand, ProjectCommand, ReleaseArgs};
+use output::OutputFormat;
+
+/// Command-line tool to interact with the upgrader canister
+#[derive(Debug, Parser)]
+#[command(name = "upgrader-cli", version, about)]
+pub struct Cli {
+    /// The principal of the upgrader canister
+    #[arg(long, env = "UPGRADER_CANISTER_ID", value_parser = parse_principal)]
+    canister: Principal,
+
+    /// The URL of the replica
+    #[arg(
+        long,
+        env = "UPGRADER_NETWORK",
+        default_value = "http://127.0.0.1:4943"
+    )]
+    network: String,
+
+    /// The PEM file of the identity used to sign the calls
+    #[arg(long, env = "UPGRADER_IDENTITY_PEM", conflicts_with = "identity")]
+    identity_pem: Option<PathBuf>,
+
+    /// The name of a dfx identity used to sign the calls
+    #[arg(long, default_value = "default")]
+    identity: String,
+
+    /// The format of the output
+    #[arg(long, value_enum, default_value_t = OutputFormat::Human)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Prints the build data of the canister
+    BuildData,
+    /// Manages the projects
+    #[command(subcommand)]
+    Project(ProjectCommand),
+    /// Manages the polls
+    #[command(subcommand)]
+    Poll(PollCommand),
+    /// Manages the permissions
+    #[command(subcommand)]
+    Permission(PermissionCommand),
+    /// Hashes a release wasm and creates a `ProjectHash` poll to approve it
+    Release(ReleaseArgs),
+}
+
+/// Parses a principal from its textual representation
+pub fn parse_principal(text: &str) -> Result<Principal, String> {
+    Principal::from_text(text).map_err(|err| format!("invalid principal [{text}]: {err}"))
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> anyhow::Result<()> {
+    let cli = Cli::parse();
+
+    let identity_path = match cli.identity_pem {
+        Some(path) => path,
+        None => identity::dfx_identity_pem(&cli.identity)?,
+    };
+    let agent_client =
+        IcAgentClient::with_identity(cli.canister, identity_path, &cli.network, None)
+            .await
+            .map_err(|err| anyhow::anyhow!("failed to build the agent: {err}"))?;
+    let client = UpgraderCanisterClient::new(agent_client);
+
+    commands::execute(&client, cli.command, cli.output).await
+}
+
+#[cfg(test)]
+mod test {
+
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_poll_vote() {
+        let cli = Cli::try_parse_from([
+            "upgrader-cli",
+            "--canister",
+            "aaaaa-aa",
+            "--output",
+            "json",
+            "poll",
+            "vote",
+            "3",
+            "--reject",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.canister, Principal::management_canister());
+        assert_eq!(cli.output, OutputFormat::Json);
+        assert!(matches!(
+            cli.command,
+            Command::Poll(PollCommand::Vote {
+                id: 3,
+                reject: true,
+                ..
+            })
+        ));
+    }
+}
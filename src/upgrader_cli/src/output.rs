@@ -0,0 +1,63 @@
+use std::fmt::Debug;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// The format of the command output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Human,
+    /// Pretty-printed JSON
+    Json,
+}
+
+/// Prints a value in the given format.
+/// The human-readable output is produced by the `human` function.
+pub fn print_with<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    human: impl FnOnce(&T) -> String,
+) -> anyhow::Result<()> {
+    println!("{}", render(format, value, human)?);
+    Ok(())
+}
+
+/// Prints a value in the given format, using its debug representation as human-readable output
+pub fn print<T: Serialize + Debug>(format: OutputFormat, value: &T) -> anyhow::Result<()> {
+    print_with(format, value, |value| format!("{value:#?}"))
+}
+
+/// Renders a value in the given format
+fn render<T: Serialize>(
+    format: OutputFormat,
+    value: &T,
+    human: impl FnOnce(&T) -> String,
+) -> anyhow::Result<String> {
+    match format {
+        OutputFormat::Human => Ok(human(value)),
+        OutputFormat::Json => Ok(serde_json::to_string_pretty(value)?),
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use candid::Principal;
+
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let value = vec![Principal::management_canister()];
+
+        assert_eq!(
+            render(OutputFormat::Json, &value, |_| String::new()).unwrap(),
+            "[\n  \"aaaaa-aa\"\n]"
+        );
+        assert_eq!(
+            render(OutputFormat::Human, &value, |value| value[0].to_text()).unwrap(),
+            "aaaaa-aa"
+        );
+    }
+}